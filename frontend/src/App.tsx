@@ -25,14 +25,24 @@ export default function App() {
     () => initialState.removedEdges,
   );
   const [addedEdges, setAddedEdges] = useState(() => initialState.addedEdges);
+  const [durationOverrides, setDurationOverrides] = useState(
+    () => initialState.durationOverrides,
+  );
   const [previewOriginal, setPreviewOriginal] = useState(false);
+  const [chainIndex, setChainIndex] = useState(0);
 
   useEffect(() => {
-    syncToHash(selectedNode?.id ?? null, removedEdges, addedEdges);
-  }, [selectedNode, removedEdges, addedEdges]);
+    syncToHash(
+      selectedNode?.id ?? null,
+      removedEdges,
+      addedEdges,
+      durationOverrides,
+    );
+  }, [selectedNode, removedEdges, addedEdges, durationOverrides]);
 
   const [modifiedTotalMs, setModifiedTotalMs] = useState<number | null>(null);
-  const hasChanges = removedEdges.size > 0 || addedEdges.size > 0;
+  const hasChanges =
+    removedEdges.size > 0 || addedEdges.size > 0 || durationOverrides.size > 0;
 
   const originalTotalMs = useMemo(() => {
     if (!graph) return 0;
@@ -83,9 +93,20 @@ export default function App() {
     });
   }, []);
 
-  const handleResetEdges = useCallback(() => {
+  const handleSetDuration = useCallback((id: string, spec: string | null) => {
+    setDurationOverrides((prev) => {
+      if ((prev.get(id) ?? null) === spec) return prev;
+      const next = new Map(prev);
+      if (spec === null) next.delete(id);
+      else next.set(id, spec);
+      return next;
+    });
+  }, []);
+
+  const handleReset = useCallback(() => {
     setRemovedEdges(new Set());
     setAddedEdges(new Set());
+    setDurationOverrides(new Map());
   }, []);
 
   if (error) return <div className="error">Error: {error}</div>;
@@ -103,6 +124,8 @@ export default function App() {
             onNodeSelect={setSelectedNode}
             removedEdges={removedEdges}
             addedEdges={addedEdges}
+            durationOverrides={durationOverrides}
+            chainIndex={chainIndex}
             onRemoveEdge={handleRemoveEdge}
             previewOriginal={previewOriginal}
             onTotalMsChange={handleTotalMsChange}
@@ -115,17 +138,24 @@ export default function App() {
             graph={graph}
             removedEdges={removedEdges}
             addedEdges={addedEdges}
+            durationOverrides={durationOverrides}
             onRemoveEdge={handleRemoveEdge}
             onAddEdge={handleAddEdge}
+            onSetDuration={handleSetDuration}
           />
         </aside>
       </main>
       {hasChanges && (
         <div className="bottom-bar">
           <span className="change-summary">
-            {removedEdges.size > 0 && `${removedEdges.size} removed`}
-            {removedEdges.size > 0 && addedEdges.size > 0 && ", "}
-            {addedEdges.size > 0 && `${addedEdges.size} added`}
+            {[
+              removedEdges.size > 0 && `${removedEdges.size} removed`,
+              addedEdges.size > 0 && `${addedEdges.size} added`,
+              durationOverrides.size > 0 &&
+                `${durationOverrides.size} retimed`,
+            ]
+              .filter(Boolean)
+              .join(", ")}
           </span>
           {modifiedTotalMs !== null &&
             (() => {
@@ -154,7 +184,7 @@ export default function App() {
           <button
             type="button"
             className="btn-reset"
-            onClick={handleResetEdges}
+            onClick={handleReset}
           >
             Reset
           </button>
@@ -162,7 +192,30 @@ export default function App() {
       )}
       <span className="timeline-legend">
         <span className="legend-swatch critical" />
-        critical path
+        {graph.longest_chains.length > 1 ? (
+          <select
+            className="chain-select"
+            value={chainIndex}
+            disabled={hasChanges}
+            title={
+              hasChanges
+                ? "The critical path is recomputed for the changed graph"
+                : "Highlight one of the longest dependency chains"
+            }
+            onChange={(e) =>
+              setChainIndex(Number((e.target as HTMLSelectElement).value))
+            }
+          >
+            {graph.longest_chains.map((chain, i) => (
+              <option key={chain.crates.join("|")} value={i}>
+                {i === 0 ? "critical path" : `chain ${i + 1}`} (
+                {(chain.length_ms / 1000).toFixed(1)}s)
+              </option>
+            ))}
+          </select>
+        ) : (
+          "critical path"
+        )}
       </span>
     </div>
   );
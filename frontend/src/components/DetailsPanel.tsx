@@ -1,13 +1,17 @@
 import { useCallback, useMemo, useRef, useState } from "preact/hooks";
+import { parseDurationOverride } from "../lib/durations.ts";
 import type { BuildGraph, CrateNode } from "../lib/types.ts";
+import { dependentsOf } from "../lib/whatif.ts";
 
 interface Props {
   node: CrateNode | null;
   graph: BuildGraph;
   removedEdges: Set<string>;
   addedEdges: Set<string>;
+  durationOverrides: Map<string, string>;
   onRemoveEdge: (from: string, to: string) => void;
   onAddEdge: (from: string, to: string) => void;
+  onSetDuration: (id: string, spec: string | null) => void;
 }
 
 function formatDuration(ms: number | null): string {
@@ -21,8 +25,10 @@ export function DetailsPanel({
   graph,
   removedEdges,
   addedEdges,
+  durationOverrides,
   onRemoveEdge,
   onAddEdge,
+  onSetDuration,
 }: Props) {
   const [addQuery, setAddQuery] = useState("");
   const [showSuggestions, setShowSuggestions] = useState(false);
@@ -81,24 +87,7 @@ export function DetailsPanel({
   // these as a dependency would create a cycle.
   const wouldCycle = useMemo(() => {
     if (!node) return new Set<string>();
-    // Build reverse map: dependency -> dependents.
-    const dependents = new Map<string, string[]>();
-    for (const e of activeEdges) {
-      if (!dependents.has(e.to)) dependents.set(e.to, []);
-      dependents.get(e.to)!.push(e.from);
-    }
-    // BFS from selected node following reverse edges.
-    const visited = new Set<string>();
-    const queue = [node.id];
-    while (queue.length > 0) {
-      const id = queue.pop()!;
-      if (visited.has(id)) continue;
-      visited.add(id);
-      for (const dep of dependents.get(id) ?? []) {
-        queue.push(dep);
-      }
-    }
-    return visited;
+    return dependentsOf(node.id, activeEdges);
   }, [node, activeEdges]);
 
   // All crate names for autocomplete, excluding already-active deps, self,
@@ -181,11 +170,27 @@ export function DetailsPanel({
     };
   }, [graph]);
 
+  const info = graph.build_info;
+  const featureFlags = [
+    info.no_default_features && "--no-default-features",
+    info.all_features && "--all-features",
+    info.features.length > 0 && `--features ${info.features.join(",")}`,
+  ]
+    .filter(Boolean)
+    .join(" ");
+
   if (!node) {
     return (
       <div className="details-panel">
         <h2>Build Summary</h2>
         <dl>
+          <dt>Measured</dt>
+          <dd>
+            <code>cargo {info.mode}</code>
+            {info.profile && ` (${info.profile})`}
+            {info.targets.length > 0 && ` for ${info.targets.join(", ")}`}
+            {info.runs > 1 && `, median of ${info.runs} runs`}
+          </dd>
           <dt>Total time</dt>
           <dd>{formatDuration(summary.totalMs)}</dd>
           <dt>Crates</dt>
@@ -205,6 +210,75 @@ export function DetailsPanel({
             </>
           )}
         </dl>
+        <h3>Build Info</h3>
+        <dl>
+          {info.rustc_version && (
+            <>
+              <dt>Toolchain</dt>
+              <dd title={info.rustc_version}>
+                {info.rustc_version.split("\n")[0]}
+              </dd>
+            </>
+          )}
+          {info.host && (
+            <>
+              <dt>Host</dt>
+              <dd>{info.host}</dd>
+            </>
+          )}
+          <dt>Jobs</dt>
+          <dd>
+            {info.jobs ?? "default"}
+            {info.available_cpus > 0 &&
+              ` (${info.available_cpus} CPUs available)`}
+          </dd>
+          {featureFlags && (
+            <>
+              <dt>Features</dt>
+              <dd>
+                <code>{featureFlags}</code>
+              </dd>
+            </>
+          )}
+          {info.rustflags && (
+            <>
+              <dt>RUSTFLAGS</dt>
+              <dd>
+                <code>{info.rustflags}</code>
+              </dd>
+            </>
+          )}
+          {info.cargo_args.length > 0 && (
+            <>
+              <dt>Cargo args</dt>
+              <dd>
+                <code>{info.cargo_args.join(" ")}</code>
+              </dd>
+            </>
+          )}
+          {info.git_commit && (
+            <>
+              <dt>Commit</dt>
+              <dd>
+                <code>{info.git_commit.slice(0, 12)}</code>
+                {info.git_dirty && " (dirty)"}
+              </dd>
+            </>
+          )}
+          {info.timings_source && (
+            <>
+              <dt>Timings from</dt>
+              <dd>{info.timings_source}</dd>
+            </>
+          )}
+          <dt>{info.timings_source ? "Built" : "Analyzed"}</dt>
+          <dd>
+            {info.timestamp > 0
+              ? new Date(info.timestamp * 1000).toLocaleString()
+              : "unknown"}{" "}
+            (goodtimes {info.goodtimes_version})
+          </dd>
+        </dl>
         <p className="details-hint">Click a bar to see crate details</p>
       </div>
     );
@@ -218,12 +292,62 @@ export function DetailsPanel({
         <dd>{node.version}</dd>
         <dt>Compile time</dt>
         <dd>{node.fresh ? "cached" : formatDuration(node.duration_ms)}</dd>
+        {node.duration_ms !== null && !node.fresh && (
+          <>
+            <dt>What-if time</dt>
+            <dd>
+              <input
+                key={node.id}
+                type="text"
+                className="duration-override"
+                placeholder="0.5x or 800ms"
+                title="Scale (0.5x) or set (800ms, 2s) this crate's compile time"
+                defaultValue={durationOverrides.get(node.id) ?? ""}
+                onInput={(e) => {
+                  const input = e.target as HTMLInputElement;
+                  const spec = input.value.trim();
+                  const valid = spec === "" || parseDurationOverride(spec);
+                  input.classList.toggle("invalid", !valid);
+                  if (valid) onSetDuration(node.id, spec || null);
+                }}
+              />
+            </dd>
+          </>
+        )}
+        {node.duration_stats && (
+          <>
+            <dt>Spread</dt>
+            <dd>
+              ±{formatDuration(node.duration_stats.stddev)} (
+              {formatDuration(node.duration_stats.min)}–
+              {formatDuration(node.duration_stats.max)})
+            </dd>
+          </>
+        )}
         {node.start_ms !== null && (
           <>
             <dt>Started at</dt>
             <dd>{formatDuration(node.start_ms)} into build</dd>
           </>
         )}
+        {node.slack_ms !== null && (
+          <>
+            <dt>Could start</dt>
+            <dd>
+              {formatDuration(node.earliest_start_ms)}–
+              {formatDuration(node.latest_start_ms)}
+            </dd>
+            <dt>Slack</dt>
+            <dd>
+              {node.slack_ms < 1
+                ? "none (on the critical path)"
+                : `${formatDuration(node.slack_ms)} (${(
+                    (100 * node.slack_ms) /
+                    Math.max(graph.critical_path_ms, 1)
+                  ).toFixed(0)}% of critical path)`}
+            </dd>
+          </>
+        )}
         <dt>Type</dt>
         <dd>{node.is_workspace_member ? "Workspace member" : "Dependency"}</dd>
         {node.features.length > 0 && (
@@ -234,6 +358,24 @@ export function DetailsPanel({
         )}
       </dl>
 
+      {node.units.length > 1 && (
+        <>
+          <h3>Units ({node.units.length})</h3>
+          <ul className="dep-list">
+            {node.units.map((unit) => (
+              <li key={`${unit.kind}|${unit.target}|${unit.platform}`}>
+                <span className="dep-name">
+                  {unit.kind} {unit.target}
+                  {graph.build_info.targets.length > 0 &&
+                    ` (${unit.platform ?? "host"})`}
+                </span>
+                <span>{formatDuration(unit.duration_ms)}</span>
+              </li>
+            ))}
+          </ul>
+        </>
+      )}
+
       <h3>Dependencies ({activeDeps.length})</h3>
       <ul className="dep-list">
         {activeDeps.map((dep) => (
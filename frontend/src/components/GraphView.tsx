@@ -6,7 +6,9 @@ import {
   useRef,
   useState,
 } from "preact/hooks";
+import { effectiveDuration } from "../lib/durations.ts";
 import type { BuildGraph, CrateNode } from "../lib/types.ts";
+import { recomputeCriticalPath, recomputeStartTimes } from "../lib/whatif.ts";
 
 const ROW_HEIGHT = 28;
 const ROW_GAP = 2;
@@ -100,6 +102,9 @@ interface Props {
   onNodeSelect: (node: CrateNode | null) => void;
   removedEdges: Set<string>;
   addedEdges: Set<string>;
+  durationOverrides: Map<string, string>;
+  /** Which of the longest chains to highlight, unless the graph was edited. */
+  chainIndex: number;
   onRemoveEdge: (from: string, to: string) => void;
   previewOriginal: boolean;
   onTotalMsChange?: (totalMs: number) => void;
@@ -109,6 +114,7 @@ interface Props {
 // Stable empty set reference to avoid infinite re-render loops when
 // previewOriginal is true (new Set() each render would destabilize all memos).
 const EMPTY_STRING_SET: Set<string> = new Set();
+const EMPTY_STRING_MAP: Map<string, string> = new Map();
 
 function formatMs(ms: number): string {
   if (ms < 1000) return `${Math.round(ms)}ms`;
@@ -121,175 +127,6 @@ const COLOR_CRITICAL = "#e8954a";
 const COLOR_LINE_DEP = "#6aadda";
 const COLOR_LINE_DEPN = "#d9944a";
 
-/**
- * Recompute start times by keeping original times and adjusting
- * crates affected by edge changes, propagating forward.
- * - Removed edges can make crates start earlier.
- * - Added edges can make crates start later.
- */
-function recomputeStartTimes(
-  nodes: Record<string, CrateNode>,
-  activeEdges: { from: string; to: string }[],
-  originalEdges: { from: string; to: string }[],
-  removedEdges: Set<string>,
-): Map<string, number> {
-  // Build active dep and dependent maps.
-  const activeDeps = new Map<string, string[]>();
-  const activeDependents = new Map<string, string[]>();
-  for (const edge of activeEdges) {
-    if (!activeDeps.has(edge.from)) activeDeps.set(edge.from, []);
-    activeDeps.get(edge.from)!.push(edge.to);
-    if (!activeDependents.has(edge.to)) activeDependents.set(edge.to, []);
-    activeDependents.get(edge.to)!.push(edge.from);
-  }
-
-  // Initialize all crates with their original start times.
-  const startTimes = new Map<string, number>();
-  for (const [id, node] of Object.entries(nodes)) {
-    startTimes.set(id, node.start_ms ?? 0);
-  }
-
-  // Find all directly affected crates (lost or gained a dependency).
-  const affected = new Set<string>();
-  for (const key of removedEdges) {
-    affected.add(key.split("|")[0]);
-  }
-  // Also check crates that gained edges (from addedEdges).
-  const originalEdgeSet = new Set(
-    originalEdges.map((e) => `${e.from}|${e.to}`),
-  );
-  for (const edge of activeEdges) {
-    const key = `${edge.from}|${edge.to}`;
-    if (!originalEdgeSet.has(key)) {
-      affected.add(edge.from);
-    }
-  }
-
-  // Seed the queue: recompute start for directly affected crates.
-  const queue: string[] = [];
-  for (const crateId of affected) {
-    const node = nodes[crateId];
-    if (!node || node.start_ms === null || node.duration_ms === null) continue;
-
-    const myDeps = activeDeps.get(crateId) ?? [];
-    let maxDepEnd = 0;
-    for (const depId of myDeps) {
-      const depNode = nodes[depId];
-      if (!depNode || depNode.duration_ms === null) continue;
-      maxDepEnd = Math.max(
-        maxDepEnd,
-        startTimes.get(depId)! + depNode.duration_ms,
-      );
-    }
-
-    const current = startTimes.get(crateId)!;
-    if (maxDepEnd !== current) {
-      startTimes.set(crateId, maxDepEnd);
-      queue.push(crateId);
-    }
-  }
-
-  // Propagate changes forward through dependents.
-  // Guard against cycles: each node is processed at most once.
-  const visited = new Set<string>();
-  while (queue.length > 0) {
-    const crateId = queue.shift()!;
-    if (visited.has(crateId)) continue;
-    visited.add(crateId);
-    const depnIds = activeDependents.get(crateId) ?? [];
-    for (const depnId of depnIds) {
-      if (visited.has(depnId)) continue;
-      const depnNode = nodes[depnId];
-      if (!depnNode || depnNode.duration_ms === null) continue;
-
-      const depnDeps = activeDeps.get(depnId) ?? [];
-      let maxDepEnd = 0;
-      for (const depId of depnDeps) {
-        const depNode = nodes[depId];
-        if (!depNode || depNode.duration_ms === null) continue;
-        maxDepEnd = Math.max(
-          maxDepEnd,
-          startTimes.get(depId)! + depNode.duration_ms,
-        );
-      }
-
-      const current = startTimes.get(depnId)!;
-      if (maxDepEnd !== current) {
-        startTimes.set(depnId, maxDepEnd);
-        queue.push(depnId);
-      }
-    }
-  }
-
-  return startTimes;
-}
-
-/** Compute critical path (longest accumulated duration path) for given edges. */
-function recomputeCriticalPath(
-  nodes: Record<string, CrateNode>,
-  edges: { from: string; to: string }[],
-): string[] {
-  const depsMap = new Map<string, string[]>();
-  for (const edge of edges) {
-    if (!depsMap.has(edge.from)) depsMap.set(edge.from, []);
-    depsMap.get(edge.from)!.push(edge.to);
-  }
-
-  const dp = new Map<string, number>();
-  const visiting = new Set<string>();
-
-  function visit(id: string): number {
-    if (dp.has(id)) return dp.get(id)!;
-    if (visiting.has(id)) return 0;
-    visiting.add(id);
-
-    const duration = nodes[id]?.duration_ms ?? 0;
-    let maxDepPath = 0;
-    const myDeps = depsMap.get(id);
-    if (myDeps) {
-      for (const depId of myDeps) {
-        maxDepPath = Math.max(maxDepPath, visit(depId));
-      }
-    }
-    const result = duration + maxDepPath;
-    dp.set(id, result);
-    return result;
-  }
-
-  for (const id of Object.keys(nodes)) visit(id);
-
-  // Find node with longest path, trace back.
-  let maxId = "";
-  let maxVal = 0;
-  for (const [id, val] of dp) {
-    if (val > maxVal) {
-      maxVal = val;
-      maxId = id;
-    }
-  }
-
-  const path: string[] = [];
-  let current = maxId;
-  while (current) {
-    path.push(current);
-    const myDeps = depsMap.get(current);
-    if (!myDeps || myDeps.length === 0) break;
-    let bestDep = "";
-    let bestVal = -1;
-    for (const depId of myDeps) {
-      const val = dp.get(depId) ?? 0;
-      if (val > bestVal) {
-        bestVal = val;
-        bestDep = depId;
-      }
-    }
-    if (bestDep === "") break;
-    current = bestDep;
-  }
-
-  return path;
-}
-
 interface TimelineEntry {
   node: CrateNode;
   startMs: number;
@@ -302,6 +139,8 @@ export function GraphView({
   onNodeSelect,
   removedEdges,
   addedEdges,
+  durationOverrides,
+  chainIndex,
   onRemoveEdge,
   previewOriginal,
   onTotalMsChange,
@@ -317,6 +156,9 @@ export function GraphView({
   // When previewing original, use unmodified edges.
   const effectiveRemoved = previewOriginal ? EMPTY_STRING_SET : removedEdges;
   const effectiveAdded = previewOriginal ? EMPTY_STRING_SET : addedEdges;
+  const effectiveOverrides = previewOriginal
+    ? EMPTY_STRING_MAP
+    : durationOverrides;
 
   // Active edges = original edges minus removed ones, plus added ones.
   const activeEdges = useMemo(() => {
@@ -347,17 +189,23 @@ export function GraphView({
 
   const { entries, totalMs, criticalPath } = useMemo(() => {
     let startTimeMap: Map<string, number> | null = null;
-    let criticalPath = graph.critical_path;
+    let criticalPath =
+      graph.longest_chains[chainIndex]?.crates ?? graph.critical_path;
 
     const hasEdgeChanges = effectiveRemoved.size > 0 || effectiveAdded.size > 0;
-    if (hasEdgeChanges) {
+    if (hasEdgeChanges || effectiveOverrides.size > 0) {
       startTimeMap = recomputeStartTimes(
         graph.nodes,
         activeEdges,
         graph.edges,
         effectiveRemoved,
+        effectiveOverrides,
+      );
+      criticalPath = recomputeCriticalPath(
+        graph.nodes,
+        activeEdges,
+        effectiveOverrides,
       );
-      criticalPath = recomputeCriticalPath(graph.nodes, activeEdges);
     }
 
     // Hide crates that become leaves through edge removal.
@@ -396,7 +244,7 @@ export function GraphView({
       .map((n) => ({
         node: n,
         startMs: startTimeMap?.get(n.id) ?? n.start_ms!,
-        durationMs: n.duration_ms!,
+        durationMs: effectiveDuration(n, effectiveOverrides)!,
         isCritical: criticalSet.has(n.id),
       }))
       .sort((a, b) => a.startMs - b.startMs || b.durationMs - a.durationMs);
@@ -407,7 +255,32 @@ export function GraphView({
     );
 
     return { entries, totalMs, criticalPath };
-  }, [graph, activeEdges, effectiveRemoved, effectiveAdded, deps]);
+  }, [
+    graph,
+    activeEdges,
+    effectiveRemoved,
+    effectiveAdded,
+    effectiveOverrides,
+    chainIndex,
+    deps,
+  ]);
+
+  const hasEdits =
+    removedEdges.size > 0 || addedEdges.size > 0 || durationOverrides.size > 0;
+
+  // Units on the original critical path, for highlighting build-script
+  // segments. Other chains are only known by crate.
+  const criticalUnitKeys = useMemo(
+    () =>
+      new Set(
+        chainIndex === 0
+          ? graph.critical_units.map(
+              (u) => `${u.id}|${u.kind}|${u.target}|${u.platform ?? "host"}`,
+            )
+          : [],
+      ),
+    [graph.critical_units, chainIndex],
+  );
 
   // Report totalMs changes to parent (skip during preview to avoid layout thrash).
   useEffect(() => {
@@ -1170,7 +1043,65 @@ export function GraphView({
                   title={`${entry.node.name}: ${formatMs(entry.durationMs)}`}
                 >
                   <span className="timeline-bar-label">{entry.node.name}</span>
+                  {entry.node.rmeta_ms !== null &&
+                    entry.node.duration_ms! > 0 && (
+                    <span
+                      className="timeline-rmeta"
+                      style={{
+                        left: `${(entry.node.rmeta_ms / entry.node.duration_ms!) * 100}%`,
+                      }}
+                      title={`metadata ready after ${formatMs(entry.node.rmeta_ms)}`}
+                    />
+                  )}
                 </div>
+                {/* Error bar: range of the crate's end over repeated runs. */}
+                {totalMs > 0 &&
+                  entry.node.duration_stats &&
+                  entry.node.duration_stats.max > entry.node.duration_stats.min && (
+                    <div
+                      className="timeline-errorbar"
+                      style={{
+                        left: `${((entry.startMs + entry.node.duration_stats.min) / totalMs) * 100}%`,
+                        width: `${((entry.node.duration_stats.max - entry.node.duration_stats.min) / totalMs) * 100}%`,
+                      }}
+                      title={`${formatMs(entry.node.duration_stats.min)}–${formatMs(entry.node.duration_stats.max)} over ${graph.build_info.runs} runs (±${formatMs(entry.node.duration_stats.stddev)})`}
+                    />
+                  )}
+                {/* Unit segments (build script, lib, bins), shifted with the bar. */}
+                {totalMs > 0 &&
+                  entry.node.units.length > 1 &&
+                  entry.node.units.map((unit) => {
+                    // Retimed crates scale all their units alike.
+                    const scale = entry.node.duration_ms
+                      ? entry.durationMs / entry.node.duration_ms
+                      : 1;
+                    const unitStart =
+                      entry.startMs +
+                      (unit.start_ms - (entry.node.start_ms ?? 0)) * scale;
+                    const unitKey = `${unit.kind}|${unit.target}|${unit.platform ?? "host"}`;
+                    const isCriticalUnit = criticalUnitKeys.has(
+                      `${entry.node.id}|${unitKey}`,
+                    );
+                    return (
+                      <div
+                        key={unitKey}
+                        className={[
+                          "timeline-unit",
+                          unit.kind,
+                          isCriticalUnit && !previewOriginal && !hasEdits
+                            ? "critical"
+                            : "",
+                        ]
+                          .filter(Boolean)
+                          .join(" ")}
+                        style={{
+                          left: `${(unitStart / totalMs) * 100}%`,
+                          width: `${Math.max(((unit.duration_ms * scale) / totalMs) * 100, 0.15)}%`,
+                        }}
+                        title={`${entry.node.name} ${unit.kind} "${unit.target}"${unit.platform ? ` (${unit.platform})` : ""}: ${formatMs(unit.duration_ms)}`}
+                      />
+                    );
+                  })}
                 {showRemoveBtn && (
                   <button
                     type="button"
@@ -1,4 +1,5 @@
 import { useMemo } from "preact/hooks";
+import { parseDurationOverride } from "../lib/durations.ts";
 
 function parseEdgeParam(value: string, validNodeIds: Set<string>): Set<string> {
   const edges = new Set<string>();
@@ -12,6 +13,31 @@ function parseEdgeParam(value: string, validNodeIds: Set<string>): Set<string> {
   return edges;
 }
 
+/** Parse `id:0.5x,id:800ms` into compile time overrides by crate ID. */
+function parseDurationParam(
+  value: string,
+  validNodeIds: Set<string>,
+): Map<string, string> {
+  const overrides = new Map<string, string>();
+  if (!value) return overrides;
+  for (const item of value.split(",")) {
+    const sep = item.lastIndexOf(":");
+    const id = item.slice(0, sep);
+    const spec = item.slice(sep + 1);
+    if (sep > 0 && validNodeIds.has(id) && parseDurationOverride(spec)) {
+      overrides.set(id, spec);
+    }
+  }
+  return overrides;
+}
+
+function serializeDurations(overrides: Map<string, string>): string {
+  return [...overrides]
+    .map(([id, spec]) => `${id}:${spec}`)
+    .sort()
+    .join(",");
+}
+
 function serializeEdges(edges: Set<string>): string {
   return [...edges]
     .map((key) => key.replace("|", "~"))
@@ -24,11 +50,15 @@ export function syncToHash(
   selectedNodeId: string | null,
   removedEdges: Set<string>,
   addedEdges: Set<string>,
+  durationOverrides: Map<string, string>,
 ) {
   const parts: string[] = [];
   if (selectedNodeId) parts.push(`sel=${selectedNodeId}`);
   if (removedEdges.size > 0) parts.push(`rm=${serializeEdges(removedEdges)}`);
   if (addedEdges.size > 0) parts.push(`add=${serializeEdges(addedEdges)}`);
+  if (durationOverrides.size > 0) {
+    parts.push(`dur=${serializeDurations(durationOverrides)}`);
+  }
 
   const hash = parts.join("&");
   const newUrl = hash
@@ -37,25 +67,35 @@ export function syncToHash(
   history.replaceState(null, "", newUrl);
 }
 
-/** Parse the URL hash on mount into initial state, validating against the graph. */
-export function useHashState(validNodeIds: Set<string>) {
-  return useMemo(() => {
-    const hash = window.location.hash.slice(1);
-    if (!hash) {
-      return {
-        selectedNodeId: null as string | null,
-        removedEdges: new Set<string>(),
-        addedEdges: new Set<string>(),
-      };
-    }
-
-    const params = new URLSearchParams(hash);
-    const sel = params.get("sel");
-
+/** Parse a URL hash (without `#`) into state, validating against the graph. */
+export function parseHash(hash: string, validNodeIds: Set<string>) {
+  if (!hash) {
     return {
-      selectedNodeId: sel && validNodeIds.has(sel) ? sel : null,
-      removedEdges: parseEdgeParam(params.get("rm") ?? "", validNodeIds),
-      addedEdges: parseEdgeParam(params.get("add") ?? "", validNodeIds),
+      selectedNodeId: null as string | null,
+      removedEdges: new Set<string>(),
+      addedEdges: new Set<string>(),
+      durationOverrides: new Map<string, string>(),
     };
-  }, [validNodeIds]);
+  }
+
+  const params = new URLSearchParams(hash);
+  const sel = params.get("sel");
+
+  return {
+    selectedNodeId: sel && validNodeIds.has(sel) ? sel : null,
+    removedEdges: parseEdgeParam(params.get("rm") ?? "", validNodeIds),
+    addedEdges: parseEdgeParam(params.get("add") ?? "", validNodeIds),
+    durationOverrides: parseDurationParam(
+      params.get("dur") ?? "",
+      validNodeIds,
+    ),
+  };
+}
+
+/** Parse the URL hash on mount into initial state, validating against the graph. */
+export function useHashState(validNodeIds: Set<string>) {
+  return useMemo(
+    () => parseHash(window.location.hash.slice(1), validNodeIds),
+    [validNodeIds],
+  );
 }
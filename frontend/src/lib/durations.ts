@@ -0,0 +1,32 @@
+import type { CrateNode } from "./types.ts";
+
+/**
+ * Parse a compile time override: a factor applied to the measured time
+ * (`0.5x`) or a new time (`800ms`, `2s`). Returns null if invalid.
+ */
+export function parseDurationOverride(
+  spec: string,
+): ((measuredMs: number) => number) | null {
+  const match = /^\s*(\d+(?:\.\d+)?)\s*(x|ms|s)\s*$/.exec(spec);
+  if (!match) return null;
+  const value = Number(match[1]);
+  switch (match[2]) {
+    case "x":
+      return (measuredMs) => measuredMs * value;
+    case "ms":
+      return () => value;
+    default:
+      return () => value * 1000;
+  }
+}
+
+/** A crate's compile time with any what-if override applied. */
+export function effectiveDuration(
+  node: CrateNode,
+  overrides: Map<string, string>,
+): number | null {
+  if (node.duration_ms === null) return null;
+  const spec = overrides.get(node.id);
+  const override = spec ? parseDurationOverride(spec) : null;
+  return override ? override(node.duration_ms) : node.duration_ms;
+}
@@ -7,6 +7,52 @@ export interface CrateNode {
   start_ms: number | null;
   fresh: boolean;
   features: string[];
+  is_proc_macro: boolean;
+  rmeta_ms: number | null;
+  units: CrateUnit[];
+  start_stats: Stats | null;
+  duration_stats: Stats | null;
+  /** Earliest start with unlimited parallelism (critical path method). */
+  earliest_start_ms: number | null;
+  /** Latest start that doesn't delay the build. */
+  latest_start_ms: number | null;
+  /** How much longer the crate could take without delaying the build. */
+  slack_ms: number | null;
+}
+
+/** Spread of a measurement over repeated runs (`--runs`). */
+export interface Stats {
+  median: number;
+  min: number;
+  max: number;
+  stddev: number;
+}
+
+export type UnitKind =
+  | "build-script"
+  | "build-script-run"
+  | "lib"
+  | "proc-macro"
+  | "bin"
+  | "test"
+  | "bench"
+  | "example"
+  | "doc";
+
+export interface CrateUnit {
+  kind: UnitKind;
+  target: string;
+  start_ms: number;
+  duration_ms: number;
+  rmeta_ms: number | null;
+  platform: string | null;
+}
+
+export interface UnitRef {
+  id: string;
+  kind: UnitKind;
+  target: string;
+  platform: string | null;
 }
 
 export interface DepEdge {
@@ -15,9 +61,44 @@ export interface DepEdge {
   dep_kinds: string[];
 }
 
+export type BuildMode = "check" | "build" | "test" | "bench" | "clippy" | "doc";
+
+export interface BuildInfo {
+  mode: BuildMode;
+  profile: string;
+  features: string[];
+  all_features: boolean;
+  no_default_features: boolean;
+  targets: string[];
+  cargo_args: string[];
+  jobs: number | null;
+  available_cpus: number;
+  rustflags: string | null;
+  rustc_version: string | null;
+  cargo_version: string | null;
+  host: string | null;
+  goodtimes_version: string;
+  timestamp: number;
+  git_commit: string | null;
+  git_dirty: boolean;
+  timings_source: string | null;
+  runs: number;
+}
+
+/** A chain of crates, each waiting on the one before. */
+export interface DependencyChain {
+  crates: string[];
+  length_ms: number;
+}
+
 export interface BuildGraph {
   nodes: Record<string, CrateNode>;
   edges: DepEdge[];
   roots: string[];
+  build_info: BuildInfo;
   critical_path: string[];
+  critical_path_ms: number;
+  critical_units: UnitRef[];
+  /** Longest distinct chains, the critical path first. */
+  longest_chains: DependencyChain[];
 }
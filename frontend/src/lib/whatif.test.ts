@@ -0,0 +1,77 @@
+import { expect, test } from "bun:test";
+import { parseHash } from "../hooks/useHashState.ts";
+import fixture from "./whatif.fixture.json";
+import type { CrateNode, DepEdge } from "./types.ts";
+import {
+  dependentsOf,
+  recomputeCriticalPath,
+  recomputeStartTimes,
+} from "./whatif.ts";
+
+// The fixture is shared with `whatif.rs`, so the report and the `what-if`
+// subcommand are checked against the same scenario.
+const id = (name: string) => `${name}@0.1.0`;
+
+const crates = fixture.crates as [string, number, number][];
+const nodes: Record<string, CrateNode> = Object.fromEntries(
+  crates.map(([name, start, duration]) => [
+    id(name),
+    {
+      id: id(name),
+      name,
+      version: "0.1.0",
+      is_workspace_member: true,
+      duration_ms: duration,
+      start_ms: start,
+      fresh: false,
+      features: [],
+      is_proc_macro: false,
+      rmeta_ms: null,
+      units: [],
+      start_stats: null,
+      duration_stats: null,
+      earliest_start_ms: null,
+      latest_start_ms: null,
+      slack_ms: null,
+    },
+  ]),
+);
+
+const edges: DepEdge[] = fixture.edges.map(([from, to, kind]) => ({
+  from: id(from),
+  to: id(to),
+  dep_kinds: [kind],
+}));
+
+test("replays a report hash like the what-if subcommand", () => {
+  const state = parseHash(fixture.hash, new Set(Object.keys(nodes)));
+  const activeEdges = [
+    ...edges.filter((e) => !state.removedEdges.has(`${e.from}|${e.to}`)),
+    ...[...state.addedEdges].map((key) => {
+      const [from, to] = key.split("|");
+      return { from, to, dep_kinds: ["normal"] };
+    }),
+  ];
+
+  const starts = recomputeStartTimes(
+    nodes,
+    activeEdges,
+    edges,
+    state.removedEdges,
+    state.durationOverrides,
+  );
+  for (const [name, start] of Object.entries(fixture.start_ms)) {
+    expect(starts.get(id(name))).toBe(start);
+  }
+
+  const path = recomputeCriticalPath(
+    nodes,
+    activeEdges,
+    state.durationOverrides,
+  );
+  expect(path.reverse()).toEqual(fixture.critical_path.map(id));
+});
+
+test("dev-dependencies don't make cycles", () => {
+  expect(dependentsOf(id("b"), edges)).toEqual(new Set([id("b"), id("d")]));
+});
@@ -0,0 +1,218 @@
+import { effectiveDuration } from "./durations.ts";
+import type { CrateNode, DepEdge } from "./types.ts";
+
+/**
+ * Whether the edge is only a dev-dependency, which gates tests, benches and
+ * examples but not the crate's library.
+ */
+export function isDevOnly(edge: DepEdge): boolean {
+  return (
+    edge.dep_kinds.length > 0 &&
+    edge.dep_kinds.every((kind) => kind === "Development")
+  );
+}
+
+/** Edges that hold back the dependent crate's library. */
+function gatingEdges(edges: DepEdge[]): DepEdge[] {
+  return edges.filter((edge) => !isDevOnly(edge));
+}
+
+/** The crate and every crate that transitively depends on it. */
+export function dependentsOf(id: string, edges: DepEdge[]): Set<string> {
+  // Build reverse map: dependency -> dependents.
+  const dependents = new Map<string, string[]>();
+  for (const e of gatingEdges(edges)) {
+    if (!dependents.has(e.to)) dependents.set(e.to, []);
+    dependents.get(e.to)!.push(e.from);
+  }
+  // BFS from the crate following reverse edges.
+  const visited = new Set<string>();
+  const queue = [id];
+  while (queue.length > 0) {
+    const current = queue.pop()!;
+    if (visited.has(current)) continue;
+    visited.add(current);
+    for (const dep of dependents.get(current) ?? []) {
+      queue.push(dep);
+    }
+  }
+  return visited;
+}
+
+/**
+ * Recompute start times by keeping original times and adjusting
+ * crates affected by edge or compile time changes, propagating forward.
+ * - Removed edges can make crates start earlier.
+ * - Added edges can make crates start later.
+ * - Retimed crates move their dependents.
+ * - Dev-dependencies are ignored: they gate tests, not the library.
+ */
+export function recomputeStartTimes(
+  nodes: Record<string, CrateNode>,
+  activeEdges: DepEdge[],
+  originalEdges: DepEdge[],
+  removedEdges: Set<string>,
+  durationOverrides: Map<string, string>,
+): Map<string, number> {
+  const durationOf = (node: CrateNode) =>
+    effectiveDuration(node, durationOverrides);
+
+  // Build active dep and dependent maps.
+  const activeDeps = new Map<string, string[]>();
+  const activeDependents = new Map<string, string[]>();
+  for (const edge of gatingEdges(activeEdges)) {
+    if (!activeDeps.has(edge.from)) activeDeps.set(edge.from, []);
+    activeDeps.get(edge.from)!.push(edge.to);
+    if (!activeDependents.has(edge.to)) activeDependents.set(edge.to, []);
+    activeDependents.get(edge.to)!.push(edge.from);
+  }
+
+  // Initialize all crates with their original start times.
+  const startTimes = new Map<string, number>();
+  for (const [id, node] of Object.entries(nodes)) {
+    startTimes.set(id, node.start_ms ?? 0);
+  }
+
+  // Find all directly affected crates (lost or gained a dependency).
+  const affected = new Set<string>();
+  const originalEdgeSet = new Set(
+    gatingEdges(originalEdges).map((e) => `${e.from}|${e.to}`),
+  );
+  for (const key of removedEdges) {
+    if (originalEdgeSet.has(key)) affected.add(key.split("|")[0]);
+  }
+  // Also check crates that gained edges (from addedEdges).
+  for (const edge of gatingEdges(activeEdges)) {
+    const key = `${edge.from}|${edge.to}`;
+    if (!originalEdgeSet.has(key)) {
+      affected.add(edge.from);
+    }
+  }
+
+  // Seed the queue: recompute start for directly affected crates.
+  const queue: string[] = [];
+  for (const crateId of affected) {
+    const node = nodes[crateId];
+    if (!node || node.start_ms === null || node.duration_ms === null) continue;
+
+    const myDeps = activeDeps.get(crateId) ?? [];
+    let maxDepEnd = 0;
+    for (const depId of myDeps) {
+      const depNode = nodes[depId];
+      const depDuration = depNode ? durationOf(depNode) : null;
+      if (depDuration === null) continue;
+      maxDepEnd = Math.max(maxDepEnd, startTimes.get(depId)! + depDuration);
+    }
+
+    const current = startTimes.get(crateId)!;
+    if (maxDepEnd !== current) {
+      startTimes.set(crateId, maxDepEnd);
+      queue.push(crateId);
+    }
+  }
+
+  // Retimed crates keep their start but move their dependents.
+  for (const crateId of durationOverrides.keys()) {
+    if (nodes[crateId]?.start_ms != null) queue.push(crateId);
+  }
+
+  // Propagate changes forward through dependents.
+  // Guard against cycles: each node is processed at most once.
+  const visited = new Set<string>();
+  while (queue.length > 0) {
+    const crateId = queue.shift()!;
+    if (visited.has(crateId)) continue;
+    visited.add(crateId);
+    const depnIds = activeDependents.get(crateId) ?? [];
+    for (const depnId of depnIds) {
+      if (visited.has(depnId)) continue;
+      const depnNode = nodes[depnId];
+      if (!depnNode || depnNode.duration_ms === null) continue;
+
+      const depnDeps = activeDeps.get(depnId) ?? [];
+      let maxDepEnd = 0;
+      for (const depId of depnDeps) {
+        const depNode = nodes[depId];
+        const depDuration = depNode ? durationOf(depNode) : null;
+        if (depDuration === null) continue;
+        maxDepEnd = Math.max(maxDepEnd, startTimes.get(depId)! + depDuration);
+      }
+
+      const current = startTimes.get(depnId)!;
+      if (maxDepEnd !== current) {
+        startTimes.set(depnId, maxDepEnd);
+        queue.push(depnId);
+      }
+    }
+  }
+
+  return startTimes;
+}
+
+/** Compute critical path (longest accumulated duration path) for given edges. */
+export function recomputeCriticalPath(
+  nodes: Record<string, CrateNode>,
+  edges: DepEdge[],
+  durationOverrides: Map<string, string>,
+): string[] {
+  const depsMap = new Map<string, string[]>();
+  for (const edge of gatingEdges(edges)) {
+    if (!depsMap.has(edge.from)) depsMap.set(edge.from, []);
+    depsMap.get(edge.from)!.push(edge.to);
+  }
+
+  const dp = new Map<string, number>();
+  const visiting = new Set<string>();
+
+  function visit(id: string): number {
+    if (dp.has(id)) return dp.get(id)!;
+    if (visiting.has(id)) return 0;
+    visiting.add(id);
+
+    const node = nodes[id];
+    const duration = node ? (effectiveDuration(node, durationOverrides) ?? 0) : 0;
+    let maxDepPath = 0;
+    const myDeps = depsMap.get(id);
+    if (myDeps) {
+      for (const depId of myDeps) {
+        maxDepPath = Math.max(maxDepPath, visit(depId));
+      }
+    }
+    const result = duration + maxDepPath;
+    dp.set(id, result);
+    return result;
+  }
+
+  for (const id of Object.keys(nodes)) visit(id);
+
+  // Find node with longest path, trace back.
+  let maxId = "";
+  let maxVal = 0;
+  for (const [id, val] of dp) {
+    if (val > maxVal) {
+      maxVal = val;
+      maxId = id;
+    }
+  }
+
+  const path: string[] = [];
+  let current = maxId;
+  while (current) {
+    path.push(current);
+    const myDeps = depsMap.get(current);
+    if (!myDeps || myDeps.length === 0) break;
+    let bestDep = "";
+    let bestVal = -1;
+    for (const depId of myDeps) {
+      const val = dp.get(depId) ?? 0;
+      if (val > bestVal) {
+        bestVal = val;
+        bestDep = depId;
+      }
+    }
+    if (bestDep === "") break;
+    current = bestDep;
+  }
+
+  return path;
+}
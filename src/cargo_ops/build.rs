@@ -1,139 +1,324 @@
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::Instant;
 
 use cargo_metadata::Message;
 
-use crate::model::{BuildGraph, CrateId, Milliseconds};
-
-/// Per-unit timing extracted from cargo's --timings HTML.
-#[derive(Debug, Clone, serde::Deserialize)]
-struct UnitTiming {
-    name: String,
-    version: String,
-    target: String,
-    start: f64,    // seconds from build start
-    duration: f64, // seconds
-}
-
-/// Apply shared cargo check flags: manifest-path, profile, and features.
-fn apply_common_args(
-    cmd: &mut Command,
-    manifest_path: &str,
-    profile: &str,
-    features: &[String],
-    all_features: bool,
-) {
-    cmd.arg("check").arg("--manifest-path").arg(manifest_path);
-
-    if profile == "release" {
+use crate::cargo_ops::info;
+use crate::cargo_ops::timings::{self, UnitArtifact, UnitTiming};
+use crate::model::{
+    BuildGraph, BuildMode, CrateId, CrateUnit, DependencyChain, Milliseconds, Stats, UnitKind,
+    UnitRef,
+};
+
+/// How many of the longest dependency chains are recorded in a report.
+pub const LONGEST_CHAINS: usize = 5;
+
+/// Cargo's package and target selection flags.
+#[derive(Debug, Clone, Default)]
+pub struct PackageSelection {
+    pub packages: Vec<String>,
+    pub workspace: bool,
+    pub exclude: Vec<String>,
+    pub bins: Vec<String>,
+    pub lib: bool,
+}
+
+impl PackageSelection {
+    fn apply(&self, cmd: &mut Command) {
+        for package in &self.packages {
+            cmd.arg("--package").arg(package);
+        }
+        if self.workspace {
+            cmd.arg("--workspace");
+        }
+        for package in &self.exclude {
+            cmd.arg("--exclude").arg(package);
+        }
+        for bin in &self.bins {
+            cmd.arg("--bin").arg(bin);
+        }
+        if self.lib {
+            cmd.arg("--lib");
+        }
+    }
+}
+
+/// Options shared by the pre-build and the timed build.
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    pub manifest_path: String,
+    pub mode: BuildMode,
+    pub profile: String,
+    /// Features to enable, either `feature` or `package/feature`.
+    pub features: Vec<String>,
+    pub all_features: bool,
+    pub no_default_features: bool,
+    /// Target triples to build for; empty for the host.
+    pub targets: Vec<String>,
+    pub selection: PackageSelection,
+    /// Extra arguments appended verbatim to every cargo build invocation.
+    pub cargo_args: Vec<String>,
+}
+
+/// Apply shared cargo flags: subcommand, manifest-path, profile, features,
+/// targets, package selection and pass-through arguments.
+fn apply_common_args(cmd: &mut Command, opts: &BuildOptions) {
+    cmd.args(opts.mode.cargo_args())
+        .arg("--manifest-path")
+        .arg(&opts.manifest_path);
+
+    if opts.profile == "release" {
         cmd.arg("--release");
-    } else if profile != "dev" {
-        cmd.arg("--profile").arg(profile);
+    } else if opts.profile != "dev" {
+        cmd.arg("--profile").arg(&opts.profile);
     }
 
-    if all_features {
+    if opts.no_default_features {
+        cmd.arg("--no-default-features");
+    }
+    if opts.all_features {
         cmd.arg("--all-features");
-    } else if !features.is_empty() {
-        cmd.arg("--features").arg(features.join(","));
+    } else if !opts.features.is_empty() {
+        cmd.arg("--features").arg(opts.features.join(","));
+    }
+
+    for target in &opts.targets {
+        cmd.arg("--target").arg(target);
     }
+
+    opts.selection.apply(cmd);
+    cmd.args(&opts.cargo_args);
 }
 
-/// Run `cargo check` without `--timings` to ensure third-party deps are compiled.
-pub fn prebuild_deps(
-    manifest_path: &str,
-    profile: &str,
-    features: &[String],
-    all_features: bool,
-) -> anyhow::Result<()> {
+/// Build without `--timings` to ensure third-party deps are compiled (and
+/// cached for the same mode as the timed build).
+pub fn prebuild_deps(opts: &BuildOptions) -> anyhow::Result<()> {
     let mut cmd = Command::new("cargo");
-    apply_common_args(&mut cmd, manifest_path, profile, features, all_features);
+    apply_common_args(&mut cmd, opts);
 
     let status = cmd.status()?;
-    anyhow::ensure!(status.success(), "cargo check (pre-build deps) failed");
+    anyhow::ensure!(
+        status.success(),
+        "cargo {} (pre-build deps) failed",
+        opts.mode
+    );
     Ok(())
 }
 
-pub fn run_build(
-    manifest_path: &str,
-    profile: &str,
-    features: &[String],
-    all_features: bool,
-) -> anyhow::Result<()> {
+/// Whether the cargo the workspace builds with accepts unstable (`-Z`)
+/// flags, honouring rustup overrides in the manifest's directory.
+fn is_nightly_cargo(manifest_path: &str) -> bool {
+    Command::new("cargo")
+        .arg("--version")
+        .current_dir(info::workspace_dir(manifest_path))
+        .output()
+        .is_ok_and(|out| {
+            let version = String::from_utf8_lossy(&out.stdout);
+            version.contains("-nightly") || version.contains("-dev")
+        })
+}
+
+/// Run the timed build and return per-unit timings.
+///
+/// On nightly the timings come from cargo's `timing-info` JSON messages; on
+/// stable (or if no such messages arrive) they are scraped from the HTML report.
+pub fn run_build(opts: &BuildOptions) -> anyhow::Result<Vec<UnitTiming>> {
     let mut cmd = Command::new("cargo");
-    apply_common_args(&mut cmd, manifest_path, profile, features, all_features);
-    cmd.arg("--message-format=json").arg("--timings");
+    apply_common_args(&mut cmd, opts);
+    cmd.arg("--message-format=json");
+    if is_nightly_cargo(&opts.manifest_path) {
+        cmd.args(["-Zunstable-options", "--timings=html,json"]);
+    } else {
+        cmd.arg("--timings");
+    }
 
     cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
 
     let mut child = cmd.spawn()?;
+    let build_start = Instant::now();
     let stdout = child
         .stdout
         .take()
         .ok_or_else(|| anyhow::anyhow!("failed to capture cargo stdout"))?;
     let reader = BufReader::new(stdout);
 
-    // Drain the message stream so cargo doesn't block on stdout.
+    // Drain the message stream so cargo doesn't block on stdout. cargo_metadata
+    // doesn't know about `timing-info`, so those arrive as unparsed text lines.
+    let mut units = Vec::new();
+    let mut artifacts = Vec::new();
     for message in Message::parse_stream(reader) {
-        let _ = message?;
+        let finished_at = build_start.elapsed().as_secs_f64();
+        match message? {
+            Message::TextLine(line) => {
+                units.extend(timings::parse_timing_info(&line, finished_at));
+            }
+            Message::CompilerArtifact(artifact) if !opts.targets.is_empty() => {
+                artifacts.extend(UnitArtifact::new(&artifact, &opts.targets, finished_at));
+            }
+            _ => {}
+        }
     }
 
     let status = child.wait()?;
-    anyhow::ensure!(status.success(), "cargo check failed");
-    Ok(())
+    anyhow::ensure!(status.success(), "cargo {} failed", opts.mode);
+
+    if units.is_empty() {
+        units = timings::load_timing_html(&default_timing_html(&opts.manifest_path)?)?;
+    }
+    timings::assign_platforms(&mut units, &artifacts);
+    Ok(units)
 }
 
-/// Parse the cargo-timings HTML and apply real per-crate timing to the graph.
-pub fn apply_timings(graph: &mut BuildGraph, manifest_path: &str) -> anyhow::Result<()> {
-    let target_dir = find_target_dir(manifest_path)?;
-    let timing_html = target_dir.join("cargo-timings").join("cargo-timing.html");
+/// Locate a cargo timing report, given either the HTML file itself or a
+/// `cargo-timings/` directory containing `cargo-timing.html`.
+pub fn resolve_timing_html(path: &Path) -> anyhow::Result<PathBuf> {
+    let timing_html = if path.is_dir() {
+        path.join("cargo-timing.html")
+    } else {
+        path.to_path_buf()
+    };
 
-    if !timing_html.exists() {
+    if !timing_html.is_file() {
         anyhow::bail!("timing HTML not found at {}", timing_html.display());
     }
+    Ok(timing_html)
+}
 
-    let html = std::fs::read_to_string(&timing_html)?;
-    let units = parse_unit_data(&html)?;
+/// Path of the timing report written by the last `--timings` build.
+pub fn default_timing_html(manifest_path: &str) -> anyhow::Result<PathBuf> {
+    let target_dir = find_target_dir(manifest_path)?;
+    resolve_timing_html(&target_dir.join("cargo-timings"))
+}
 
-    // Aggregate per crate (name, version) — a crate may have multiple units
-    // (lib, build-script, proc-macro, bin).
-    // Use only lib/check/bin targets for timeline positioning; build scripts
-    // compile early and would misplace the crate in the timeline.
-    let mut lib_timings: HashMap<(String, String), (f64, f64)> = HashMap::new();
-    let mut all_timings: HashMap<(String, String), (f64, f64)> = HashMap::new();
-    for unit in &units {
-        let key = (unit.name.clone(), unit.version.clone());
+/// Apply real per-unit timing to the graph.
+pub fn apply_timings(graph: &mut BuildGraph, units: &[UnitTiming]) {
+    // Group units per crate (name, version) — a crate may have multiple units
+    // (build script compile and run, lib or proc-macro, bins, tests).
+    let mut crate_units: HashMap<(&str, &str), Vec<CrateUnit>> = HashMap::new();
+    for unit in units {
+        let (kind, target) = unit.kind();
+        crate_units
+            .entry((&unit.name, &unit.version))
+            .or_default()
+            .push(CrateUnit {
+                kind,
+                target,
+                start_ms: (unit.start * 1000.0).into(),
+                duration_ms: (unit.duration * 1000.0).into(),
+                rmeta_ms: unit.rmeta_time.map(|t| (t * 1000.0).into()),
+                platform: unit.platform.clone(),
+            });
+    }
 
-        // Fallback: aggregate across all units.
-        let all_entry = all_timings.entry(key.clone()).or_insert((f64::MAX, 0.0));
-        all_entry.0 = all_entry.0.min(unit.start);
-        all_entry.1 += unit.duration;
+    for node in graph.nodes.values_mut() {
+        let Some(mut units) = crate_units.remove(&(node.name.as_str(), node.version.as_str()))
+        else {
+            continue;
+        };
+        for unit in &mut units {
+            if node.is_proc_macro && unit.kind == UnitKind::Lib {
+                unit.kind = UnitKind::ProcMacro;
+            }
+        }
+        units.sort_by(|a, b| {
+            a.start_ms
+                .partial_cmp(&b.start_ms)
+                .unwrap_or(Ordering::Equal)
+        });
 
-        // Preferred: only non-build-script units (lib, bin, proc-macro checks).
-        if !unit.target.contains("build script") {
-            let lib_entry = lib_timings.entry(key).or_insert((f64::MAX, 0.0));
-            lib_entry.0 = lib_entry.0.min(unit.start);
-            lib_entry.1 += unit.duration;
+        // Position the crate by its own targets; build scripts compile early
+        // and are shown as separate segments. The span is measured rather than
+        // summed so units that ran in parallel aren't counted twice.
+        let has_targets = units.iter().any(|u| u.kind.stage() >= 2);
+        let positioned = units.iter().filter(|u| !has_targets || u.kind.stage() >= 2);
+        let start = positioned
+            .clone()
+            .map(|u| f64::from(u.start_ms))
+            .fold(f64::MAX, f64::min);
+        let end = positioned
+            .map(|u| f64::from(u.start_ms + u.duration_ms))
+            .fold(0.0, f64::max);
+        let busy: f64 = units.iter().map(|u| f64::from(u.duration_ms)).sum();
+
+        node.start_ms = Some(start.into());
+        node.duration_ms = Some((end - start).max(0.0).into());
+        node.rmeta_ms = units
+            .iter()
+            .find(|u| u.kind == UnitKind::Lib)
+            .and_then(|u| Some(f64::from(u.start_ms) + f64::from(u.rmeta_ms?) - start))
+            .map(Milliseconds::from);
+        node.fresh = busy < 1.0; // effectively zero = cached
+        node.units = units;
+    }
+
+    compute_critical_path(graph);
+}
+
+/// Apply timings from several runs of the same build. Each unit is placed at
+/// its median start and duration, so the critical path is computed on
+/// medians, and each crate records how much it varied between runs.
+pub fn apply_runs(graph: &mut BuildGraph, runs: &[Vec<UnitTiming>]) {
+    // Each crate's measured position in every run.
+    let mut starts: HashMap<CrateId, Vec<f64>> = HashMap::new();
+    let mut durations: HashMap<CrateId, Vec<f64>> = HashMap::new();
+    for run in runs {
+        let mut measured = graph.clone();
+        apply_timings(&mut measured, run);
+        for node in measured.nodes.into_values() {
+            let (Some(start), Some(duration)) = (node.start_ms, node.duration_ms) else {
+                continue;
+            };
+            starts
+                .entry(node.id.clone())
+                .or_default()
+                .push(start.into());
+            durations.entry(node.id).or_default().push(duration.into());
         }
     }
 
-    // Match timings to graph nodes by name + version, preferring lib timings.
+    apply_timings(graph, &median_units(runs));
     for node in graph.nodes.values_mut() {
-        let key = (node.name.clone(), node.version.clone());
-        let timing = lib_timings.get(&key).or_else(|| all_timings.get(&key));
-        if let Some(&(start, duration)) = timing {
-            node.start_ms = Some((start * 1000.0).into());
-            node.duration_ms = Some((duration * 1000.0).into());
-            node.fresh = duration < 0.001; // effectively zero = cached
-        }
+        node.start_stats = starts.get(&node.id).and_then(|s| Stats::from_samples(s));
+        node.duration_stats = durations.get(&node.id).and_then(|d| Stats::from_samples(d));
     }
+}
 
-    compute_critical_path(graph);
-    Ok(())
+/// One timing per unit, at the median of its start, duration and metadata
+/// time over the runs it appeared in.
+fn median_units(runs: &[Vec<UnitTiming>]) -> Vec<UnitTiming> {
+    let mut order = Vec::new();
+    let mut samples: HashMap<_, Vec<&UnitTiming>> = HashMap::new();
+    for unit in runs.iter().flatten() {
+        let key = (&unit.name, &unit.version, &unit.target, &unit.platform);
+        samples
+            .entry(key)
+            .or_insert_with(|| {
+                order.push(key);
+                Vec::new()
+            })
+            .push(unit);
+    }
+
+    let median = |values: Vec<f64>| Stats::from_samples(&values).map(|s| f64::from(s.median));
+    order
+        .into_iter()
+        .map(|key| {
+            let units = &samples[&key];
+            UnitTiming {
+                start: median(units.iter().map(|u| u.start).collect()).unwrap_or_default(),
+                duration: median(units.iter().map(|u| u.duration).collect()).unwrap_or_default(),
+                rmeta_time: median(units.iter().filter_map(|u| u.rmeta_time).collect()),
+                ..units[0].clone()
+            }
+        })
+        .collect()
 }
 
-pub fn find_target_dir(manifest_path: &str) -> anyhow::Result<std::path::PathBuf> {
+pub fn find_target_dir(manifest_path: &str) -> anyhow::Result<PathBuf> {
     let metadata = cargo_metadata::MetadataCommand::new()
         .manifest_path(manifest_path)
         .no_deps()
@@ -141,102 +326,725 @@ pub fn find_target_dir(manifest_path: &str) -> anyhow::Result<std::path::PathBuf
     Ok(Path::new(&metadata.target_directory).to_path_buf())
 }
 
-/// Extract UNIT_DATA JSON array from the cargo-timing HTML.
-fn parse_unit_data(html: &str) -> anyhow::Result<Vec<UnitTiming>> {
-    // The HTML contains: const UNIT_DATA = [{...}, ...];
-    let start_marker = "const UNIT_DATA = ";
-    let start_idx = html
-        .find(start_marker)
-        .ok_or_else(|| anyhow::anyhow!("UNIT_DATA not found in timing HTML"))?;
-    let rest = &html[start_idx + start_marker.len()..];
-    let end_idx = rest
-        .find("];")
-        .ok_or_else(|| anyhow::anyhow!("UNIT_DATA end not found"))?;
-    let json_str = &rest[..=end_idx]; // include the closing ]
-
-    let units: Vec<UnitTiming> = serde_json::from_str(json_str)?;
-    Ok(units)
+/// A compilation unit in the [`UnitDag`].
+pub(crate) struct DagUnit {
+    pub unit: UnitRef,
+    pub duration: Milliseconds,
+    /// Offset from the unit's start at which its metadata (`.rmeta`) was ready.
+    pub rmeta: Option<Milliseconds>,
 }
 
-/// Compute the critical path: the longest chain by accumulated compile time.
-fn compute_critical_path(graph: &mut BuildGraph) {
-    // Build reverse adjacency: dependency -> vec of dependents.
-    let mut dependents: HashMap<&CrateId, Vec<&CrateId>> = HashMap::new();
-    for edge in &graph.edges {
-        dependents.entry(&edge.to).or_default().push(&edge.from);
+impl DagUnit {
+    /// How long after this unit starts a dependent waiting on it may start.
+    pub fn ready_after(&self, metadata_only: bool) -> Milliseconds {
+        match self.rmeta {
+            Some(rmeta) if metadata_only => rmeta,
+            _ => self.duration,
+        }
     }
+}
+
+/// An edge in the [`UnitDag`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DagDep {
+    pub unit: usize,
+    /// With pipelined compilation, a library only waits for the metadata of
+    /// the libraries it depends on, not for their codegen to finish.
+    pub metadata_only: bool,
+}
 
-    let mut cost: HashMap<CrateId, Milliseconds> = HashMap::new();
-    let mut next_on_path: HashMap<CrateId, CrateId> = HashMap::new();
+/// Unit-level dependency graph derived from a [`BuildGraph`].
+///
+/// Within a crate, the build script is compiled, then run, then the library is
+/// built, and binaries/tests come last. Across crates, build dependencies gate
+/// the build script, normal dependencies gate the library and dev-dependencies
+/// gate tests, benches and examples.
+pub(crate) struct UnitDag {
+    pub units: Vec<DagUnit>,
+    /// For each unit, the units it waits for.
+    pub deps: Vec<Vec<DagDep>>,
+}
 
-    fn longest(
-        id: &CrateId,
-        nodes: &HashMap<CrateId, crate::model::CrateNode>,
-        dependents: &HashMap<&CrateId, Vec<&CrateId>>,
-        cost: &mut HashMap<CrateId, Milliseconds>,
-        next_on_path: &mut HashMap<CrateId, CrateId>,
-    ) -> Milliseconds {
-        if let Some(c) = cost.get(id) {
-            return *c;
+impl UnitDag {
+    pub fn new(graph: &BuildGraph) -> Self {
+        let mut units = Vec::new();
+        let mut deps: Vec<Vec<DagDep>> = Vec::new();
+        // Unit indices of each crate, by stage.
+        let mut stages: HashMap<&CrateId, [Vec<usize>; 4]> = HashMap::new();
+
+        let mut ids: Vec<&CrateId> = graph.nodes.keys().collect();
+        ids.sort();
+        for id in ids {
+            let node = &graph.nodes[id];
+            let mut crate_stages: [Vec<usize>; 4] = Default::default();
+            let mut push = |unit: UnitRef, duration: Milliseconds, rmeta: Option<Milliseconds>| {
+                crate_stages[usize::from(unit.kind.stage())].push(units.len());
+                units.push(DagUnit {
+                    unit,
+                    duration,
+                    rmeta,
+                });
+                deps.push(Vec::new());
+            };
+            if node.units.is_empty() {
+                // Untimed crates still connect their dependents and dependencies.
+                let kind = if node.is_proc_macro {
+                    UnitKind::ProcMacro
+                } else {
+                    UnitKind::Lib
+                };
+                let unit = UnitRef {
+                    id: id.clone(),
+                    kind,
+                    target: node.name.clone(),
+                    platform: None,
+                };
+                push(unit, node.duration_ms.unwrap_or(Milliseconds::zero()), None);
+            }
+            for u in &node.units {
+                let unit = UnitRef {
+                    id: id.clone(),
+                    kind: u.kind,
+                    target: u.target.clone(),
+                    platform: u.platform.clone(),
+                };
+                push(unit, u.duration_ms, u.rmeta_ms);
+            }
+
+            // Chain each stage to the closest earlier stage present.
+            let mut prev: Option<usize> = None;
+            for stage in 0..crate_stages.len() {
+                if crate_stages[stage].is_empty() {
+                    continue;
+                }
+                if let Some(prev) = prev {
+                    for &u in &crate_stages[stage] {
+                        let ready = same_platform(&units, u, &crate_stages[prev]);
+                        deps[u].extend(ready.into_iter().map(|d| DagDep {
+                            unit: d,
+                            metadata_only: false,
+                        }));
+                    }
+                }
+                prev = Some(stage);
+            }
+            stages.insert(id, crate_stages);
         }
 
-        let self_dur = nodes
-            .get(id)
-            .and_then(|n| n.duration_ms)
-            .unwrap_or(Milliseconds::zero());
+        // The units that gate a crate's dependents: its library, or everything
+        // in its last stage if it has none.
+        let provides = |stages: &[Vec<usize>; 4]| -> Vec<usize> {
+            if !stages[2].is_empty() {
+                stages[2].clone()
+            } else {
+                stages
+                    .iter()
+                    .rev()
+                    .find(|s| !s.is_empty())
+                    .cloned()
+                    .unwrap_or_default()
+            }
+        };
+        // The first units of a crate at or after `stage`.
+        let entry = |stages: &[Vec<usize>; 4], stage: usize| -> Vec<usize> {
+            stages[stage..]
+                .iter()
+                .chain(stages[..stage].iter().rev())
+                .find(|s| !s.is_empty())
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        for edge in &graph.edges {
+            let (Some(from), Some(to)) = (stages.get(&edge.from), stages.get(&edge.to)) else {
+                continue;
+            };
+            let ready = provides(to);
+            let mut gated = Vec::new();
+            if edge.has_kind("Build") {
+                gated.extend(entry(from, 0));
+            }
+            if edge.is_normal() {
+                gated.extend(entry(from, 2));
+            } else if edge.has_kind("Development") {
+                gated.extend(from[3].iter().copied().filter(|&u| {
+                    matches!(
+                        units[u].unit.kind,
+                        UnitKind::Test | UnitKind::Bench | UnitKind::Example
+                    )
+                }));
+            }
+            for u in gated {
+                // Binaries, proc-macros and build scripts link their
+                // dependencies and need them fully built.
+                let pipelined = units[u].unit.kind == UnitKind::Lib;
+                let ready = same_platform(&units, u, &ready);
+                deps[u].extend(ready.into_iter().map(|d| DagDep {
+                    unit: d,
+                    metadata_only: pipelined && units[d].unit.kind == UnitKind::Lib,
+                }));
+            }
+        }
 
-        let mut best_child_cost = Milliseconds::zero();
-        let mut best_child: Option<&CrateId> = None;
+        UnitDag { units, deps }
+    }
 
-        if let Some(deps) = dependents.get(id) {
-            for dep_id in deps {
-                let c = longest(dep_id, nodes, dependents, cost, next_on_path);
-                if c > best_child_cost {
-                    best_child_cost = c;
-                    best_child = Some(dep_id);
+    /// Units in dependency order. Units on a cycle are left out.
+    pub fn topo_order(&self) -> Vec<usize> {
+        let mut pending: Vec<usize> = self.deps.iter().map(Vec::len).collect();
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); self.units.len()];
+        for (u, deps) in self.deps.iter().enumerate() {
+            for dep in deps {
+                dependents[dep.unit].push(u);
+            }
+        }
+        let mut order: Vec<usize> = (0..self.units.len()).filter(|&u| pending[u] == 0).collect();
+        let mut i = 0;
+        while i < order.len() {
+            for &u in &dependents[order[i]] {
+                pending[u] -= 1;
+                if pending[u] == 0 {
+                    order.push(u);
                 }
             }
+            i += 1;
         }
+        order
+    }
+}
 
-        let total = self_dur + best_child_cost;
-        cost.insert(id.clone(), total);
-        if let Some(child) = best_child {
-            next_on_path.insert(id.clone(), child.clone());
+/// The candidates built for the same platform as unit `u`. Falls back to all
+/// candidates when there are none, e.g. for host-only proc-macros.
+fn same_platform(units: &[DagUnit], u: usize, candidates: &[usize]) -> Vec<usize> {
+    let platform = &units[u].unit.platform;
+    let matching: Vec<usize> = candidates
+        .iter()
+        .copied()
+        .filter(|&d| &units[d].unit.platform == platform)
+        .collect();
+    if matching.is_empty() {
+        candidates.to_vec()
+    } else {
+        matching
+    }
+}
+
+/// Earliest start and finish of every unit, assuming unlimited parallelism.
+struct Schedule {
+    start: Vec<Milliseconds>,
+    finish: Vec<Milliseconds>,
+    /// The dependency that determined each unit's start.
+    blocked_by: Vec<Option<usize>>,
+}
+
+fn earliest_schedule(dag: &UnitDag, order: &[usize]) -> Schedule {
+    let mut start = vec![Milliseconds::zero(); dag.units.len()];
+    let mut finish = vec![Milliseconds::zero(); dag.units.len()];
+    let mut blocked_by: Vec<Option<usize>> = vec![None; dag.units.len()];
+    for &u in order {
+        for dep in &dag.deps[u] {
+            let ready = start[dep.unit] + dag.units[dep.unit].ready_after(dep.metadata_only);
+            if blocked_by[u].is_none() || ready > start[u] {
+                start[u] = ready;
+                blocked_by[u] = Some(dep.unit);
+            }
         }
-        total
+        finish[u] = start[u] + dag.units[u].duration;
+    }
+    Schedule {
+        start,
+        finish,
+        blocked_by,
     }
+}
+
+/// Record each timed crate's earliest and latest start and slack (CPM),
+/// assuming unlimited parallelism. A crate starts with its own targets, like
+/// `start_ms`, and its slack is the smallest of any of its units.
+fn schedule_crates(graph: &mut BuildGraph, dag: &UnitDag, order: &[usize], schedule: &Schedule) {
+    let end = schedule
+        .finish
+        .iter()
+        .copied()
+        .fold(0.0, |max, f| f64::max(max, f.into()));
 
-    let has_deps: HashSet<&CrateId> = graph.edges.iter().map(|e| &e.from).collect();
-    let leaves: Vec<CrateId> = graph
-        .nodes
-        .keys()
-        .filter(|id| !has_deps.contains(id))
-        .cloned()
+    // Backward pass: the latest each unit may start without delaying the end
+    // of the build or any of its dependents.
+    let mut latest: Vec<f64> = dag
+        .units
+        .iter()
+        .map(|u| end - f64::from(u.duration))
         .collect();
+    for &u in order.iter().rev() {
+        for dep in &dag.deps[u] {
+            let ready = f64::from(dag.units[dep.unit].ready_after(dep.metadata_only));
+            latest[dep.unit] = latest[dep.unit].min(latest[u] - ready);
+        }
+    }
 
-    let all_ids: Vec<CrateId> = graph.nodes.keys().cloned().collect();
-    for id in &all_ids {
-        longest(id, &graph.nodes, &dependents, &mut cost, &mut next_on_path);
+    // Per crate: (earliest start of its targets, of any unit, smallest slack).
+    let mut crates: HashMap<&CrateId, (f64, f64, f64)> = HashMap::new();
+    for &u in order {
+        let unit = &dag.units[u].unit;
+        let start = f64::from(schedule.start[u]);
+        let slack = (latest[u] - start).max(0.0);
+        let target_start = if unit.kind.stage() >= 2 {
+            start
+        } else {
+            f64::MAX
+        };
+        let entry = crates
+            .entry(&unit.id)
+            .or_insert((f64::MAX, f64::MAX, f64::MAX));
+        entry.0 = entry.0.min(target_start);
+        entry.1 = entry.1.min(start);
+        entry.2 = entry.2.min(slack);
     }
+    for node in graph.nodes.values_mut() {
+        let scheduled = crates.get(&node.id).filter(|_| node.duration_ms.is_some());
+        let Some(&(target_start, any_start, slack)) = scheduled else {
+            node.earliest_start_ms = None;
+            node.latest_start_ms = None;
+            node.slack_ms = None;
+            continue;
+        };
+        let earliest = if target_start < f64::MAX {
+            target_start
+        } else {
+            any_start
+        };
+        node.earliest_start_ms = Some(earliest.into());
+        node.latest_start_ms = Some((earliest + slack).into());
+        node.slack_ms = Some(slack.into());
+    }
+}
 
-    let start = leaves.iter().chain(all_ids.iter()).max_by(|a, b| {
-        cost.get(*a)
-            .unwrap_or(&Milliseconds::zero())
-            .partial_cmp(cost.get(*b).unwrap_or(&Milliseconds::zero()))
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+/// Compute the critical path: the longest chain by accumulated compile time,
+/// assuming unlimited parallelism, and each crate's slack.
+pub fn compute_critical_path(graph: &mut BuildGraph) {
+    let dag = UnitDag::new(graph);
+    let order = dag.topo_order();
+    let schedule = earliest_schedule(&dag, &order);
+    let chain = critical_chain(&schedule);
+    let units: Vec<UnitRef> = chain.iter().map(|&u| dag.units[u].unit.clone()).collect();
 
-    let mut path = Vec::new();
-    if let Some(start) = start {
-        let mut cur = start.clone();
-        loop {
-            path.push(cur.clone());
-            match next_on_path.get(&cur) {
-                Some(next) => cur = next.clone(),
-                None => break,
-            }
+    let mut path: Vec<CrateId> = Vec::new();
+    for unit in &units {
+        if path.last() != Some(&unit.id) {
+            path.push(unit.id.clone());
         }
     }
 
+    let length_ms = chain
+        .last()
+        .map_or(Milliseconds::zero(), |&u| schedule.finish[u]);
+
+    // Ties may be broken differently when enumerating; lead with the
+    // critical path.
+    let mut chains = longest_chains(&dag, &order, LONGEST_CHAINS + 1);
+    chains.retain(|c| c.crates != path);
+    if !path.is_empty() {
+        chains.insert(
+            0,
+            DependencyChain {
+                crates: path.clone(),
+                length_ms,
+            },
+        );
+    }
+    chains.truncate(LONGEST_CHAINS);
+
     graph.critical_path = path;
+    graph.critical_units = units;
+    graph.critical_path_ms = length_ms;
+    graph.longest_chains = chains;
+    schedule_crates(graph, &dag, &order, &schedule);
+}
+
+/// The units on the critical path, in build order.
+fn critical_chain(schedule: &Schedule) -> Vec<usize> {
+    let last = (0..schedule.finish.len()).max_by(|&a, &b| {
+        schedule.finish[a]
+            .partial_cmp(&schedule.finish[b])
+            .unwrap_or(Ordering::Equal)
+    });
+    let mut chain = Vec::new();
+    let mut cur = last;
+    while let Some(u) = cur {
+        chain.push(u);
+        cur = schedule.blocked_by[u];
+    }
+    chain.reverse();
+    chain
+}
+
+/// The `k` longest distinct chains of crates through the build, assuming
+/// unlimited parallelism, longest first.
+///
+/// Each unit keeps its `k` longest chains back to a unit without
+/// dependencies, and a chain ends at a unit when continuing to any of its
+/// dependents would not make it longer. Chains through the same crates via
+/// different units, e.g. a build script and a library, count once.
+fn longest_chains(dag: &UnitDag, order: &[usize], k: usize) -> Vec<DependencyChain> {
+    let duration = |u: usize| f64::from(dag.units[u].duration);
+    let mut dependents: Vec<Vec<(usize, f64)>> = vec![Vec::new(); dag.units.len()];
+    for (u, deps) in dag.deps.iter().enumerate() {
+        for dep in deps {
+            let ready = dag.units[dep.unit].ready_after(dep.metadata_only);
+            dependents[dep.unit].push((u, ready.into()));
+        }
+    }
+
+    // Chains ending at each unit, as (start of the unit, units).
+    let mut best: Vec<Vec<(f64, Vec<usize>)>> = vec![Vec::new(); dag.units.len()];
+    let mut ends: Vec<(f64, Vec<usize>)> = Vec::new();
+    for &u in order {
+        let chains = if dag.deps[u].is_empty() {
+            vec![(0.0, vec![u])]
+        } else {
+            let candidates = dag.deps[u]
+                .iter()
+                .flat_map(|dep| {
+                    let ready = f64::from(dag.units[dep.unit].ready_after(dep.metadata_only));
+                    best[dep.unit].iter().map(move |(start, units)| {
+                        let mut units = units.clone();
+                        units.push(u);
+                        (start + ready, units)
+                    })
+                })
+                .collect();
+            distinct_longest(dag, candidates, k)
+        };
+        if dependents[u]
+            .iter()
+            .all(|&(d, ready)| ready + duration(d) < duration(u))
+        {
+            ends.extend(
+                chains
+                    .iter()
+                    .map(|(start, units)| (start + duration(u), units.clone())),
+            );
+        }
+        best[u] = chains;
+    }
+
+    distinct_longest(dag, ends, k)
+        .into_iter()
+        .map(|(length, units)| DependencyChain {
+            crates: chain_crates(dag, &units).into_iter().cloned().collect(),
+            length_ms: length.into(),
+        })
+        .collect()
+}
+
+/// The `k` longest of `chains`, keeping only the longest through each
+/// sequence of crates.
+fn distinct_longest(
+    dag: &UnitDag,
+    mut chains: Vec<(f64, Vec<usize>)>,
+    k: usize,
+) -> Vec<(f64, Vec<usize>)> {
+    chains.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut seen = HashSet::new();
+    chains.retain(|(_, units)| seen.insert(chain_crates(dag, units)));
+    chains.truncate(k);
+    chains
+}
+
+/// The crates a chain of units passes through, in order.
+fn chain_crates<'a>(dag: &'a UnitDag, units: &[usize]) -> Vec<&'a CrateId> {
+    let mut crates: Vec<&CrateId> = Vec::new();
+    for &u in units {
+        let id = &dag.units[u].unit.id;
+        if crates.last() != Some(&id) {
+            crates.push(id);
+        }
+    }
+    crates
+}
+
+/// The critical path by crate, with the time each crate contributes to it.
+/// With pipelining this can be less than the crate's compile time, as the
+/// next crate starts once metadata is ready. The times add up to
+/// `critical_path_ms`.
+pub fn critical_path_times(graph: &BuildGraph) -> Vec<(CrateId, Milliseconds)> {
+    let dag = UnitDag::new(graph);
+    let schedule = earliest_schedule(&dag, &dag.topo_order());
+    let chain = critical_chain(&schedule);
+
+    let mut times: Vec<(CrateId, Milliseconds)> = Vec::new();
+    for (i, &u) in chain.iter().enumerate() {
+        let until = chain
+            .get(i + 1)
+            .map_or(schedule.finish[u], |&next| schedule.start[next]);
+        let time = Milliseconds::from(f64::from(until) - f64::from(schedule.start[u]));
+        let id = &dag.units[u].unit.id;
+        match times.last_mut() {
+            Some((last, total)) if last == id => *total = *total + time,
+            _ => times.push((id.clone(), time)),
+        }
+    }
+    times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::{self, graph, unit};
+
+    fn pipelined(name: &str, start: f64, duration: f64, rmeta: f64) -> UnitTiming {
+        UnitTiming {
+            rmeta_time: Some(rmeta),
+            ..unit(name, "", start, duration)
+        }
+    }
+
+    fn names(path: &[CrateId], graph: &BuildGraph) -> Vec<String> {
+        path.iter().map(|id| graph.nodes[id].name.clone()).collect()
+    }
+
+    #[test]
+    fn crate_span_excludes_build_script_and_does_not_sum() {
+        let mut g = graph(&["a"], &[]);
+        apply_timings(
+            &mut g,
+            &[
+                unit("a", " build-script", 0.0, 1.0),
+                unit("a", " build-script (run)", 1.0, 0.5),
+                unit("a", "", 1.5, 2.0),
+                unit("a", " bin \"x\"", 3.5, 1.0),
+                unit("a", " bin \"y\"", 3.5, 0.5),
+            ],
+        );
+        let node = g.nodes.values().next().unwrap();
+        assert_eq!(5, node.units.len());
+        assert_eq!(Some(1500.0.into()), node.start_ms);
+        assert_eq!(Some(3000.0.into()), node.duration_ms);
+        assert_eq!(UnitKind::BuildScript, node.units[0].kind);
+    }
+
+    #[test]
+    fn critical_path_follows_normal_deps() {
+        let mut g = graph(
+            &["a", "b", "c"],
+            &[("b", "a", "Normal"), ("c", "b", "Normal")],
+        );
+        apply_timings(
+            &mut g,
+            &[
+                unit("a", "", 0.0, 1.0),
+                unit("b", "", 1.0, 1.0),
+                unit("c", "", 2.0, 1.0),
+            ],
+        );
+        assert_eq!(vec!["a", "b", "c"], names(&g.critical_path, &g));
+        assert_eq!(Milliseconds::from(3000.0), g.critical_path_ms);
+        assert_eq!(Milliseconds::from(3000.0), g.total_ms());
+    }
+
+    #[test]
+    fn slack_is_zero_on_the_critical_path() {
+        // c only needs a, and could start up to 2s later than it does.
+        let mut g = graph(
+            &["a", "b", "c", "d"],
+            &[
+                ("b", "a", "Normal"),
+                ("c", "a", "Normal"),
+                ("d", "b", "Normal"),
+            ],
+        );
+        apply_timings(
+            &mut g,
+            &[
+                unit("a", "", 0.0, 1.0),
+                unit("b", "", 1.0, 2.0),
+                unit("c", "", 1.0, 1.0),
+                unit("d", "", 3.0, 1.0),
+            ],
+        );
+        let slack_of = |name: &str| f64::from(g.nodes[&fixtures::id(name)].slack_ms.unwrap());
+        assert_eq!(0.0, slack_of("a"));
+        assert_eq!(0.0, slack_of("b"));
+        assert_eq!(2000.0, slack_of("c"));
+        assert_eq!(0.0, slack_of("d"));
+        let c = &g.nodes[&fixtures::id("c")];
+        assert_eq!(Some(Milliseconds::from(1000.0)), c.earliest_start_ms);
+        assert_eq!(Some(Milliseconds::from(3000.0)), c.latest_start_ms);
+
+        // c's 2s of slack is half the 4s critical path.
+        let near = |percent: f64| -> Vec<String> {
+            g.near_critical(percent)
+                .iter()
+                .map(|n| n.name.clone())
+                .collect()
+        };
+        assert_eq!(vec!["c"], near(50.0));
+        assert!(near(40.0).is_empty());
+    }
+
+    #[test]
+    fn longest_chains_are_distinct_and_ordered() {
+        // Three ways to d: through b (4s), through c (3s) and directly from
+        // a (2s). e is built alongside in 0.5s.
+        let mut g = graph(
+            &["a", "b", "c", "d", "e"],
+            &[
+                ("b", "a", "Normal"),
+                ("c", "a", "Normal"),
+                ("d", "a", "Normal"),
+                ("d", "b", "Normal"),
+                ("d", "c", "Normal"),
+            ],
+        );
+        apply_timings(
+            &mut g,
+            &[
+                unit("a", "", 0.0, 1.0),
+                unit("b", "", 1.0, 2.0),
+                unit("c", "", 1.0, 1.0),
+                unit("d", "", 3.0, 1.0),
+                unit("e", "", 0.0, 0.5),
+            ],
+        );
+        let chains: Vec<(Vec<String>, f64)> = g
+            .longest_chains
+            .iter()
+            .map(|c| (names(&c.crates, &g), c.length_ms.into()))
+            .collect();
+        let chain =
+            |names: &[&str], length: f64| (names.iter().map(|n| n.to_string()).collect(), length);
+        assert_eq!(
+            vec![
+                chain(&["a", "b", "d"], 4000.0),
+                chain(&["a", "c", "d"], 3000.0),
+                chain(&["a", "d"], 2000.0),
+                chain(&["e"], 500.0),
+            ],
+            chains
+        );
+        assert_eq!(g.critical_path, g.longest_chains[0].crates);
+    }
+
+    #[test]
+    fn critical_path_runs_through_build_script() {
+        // b's build script doesn't wait for a, and takes longer than a.
+        let mut g = graph(&["a", "b"], &[("b", "a", "Normal")]);
+        apply_timings(
+            &mut g,
+            &[
+                unit("a", "", 0.0, 0.1),
+                unit("b", " build-script", 0.0, 0.5),
+                unit("b", " build-script (run)", 0.5, 0.01),
+                unit("b", "", 0.51, 0.05),
+            ],
+        );
+        assert_eq!(vec!["b"], names(&g.critical_path, &g));
+        let kinds: Vec<UnitKind> = g.critical_units.iter().map(|u| u.kind).collect();
+        assert_eq!(
+            vec![
+                UnitKind::BuildScript,
+                UnitKind::BuildScriptRun,
+                UnitKind::Lib
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn build_dependency_gates_build_script() {
+        let mut g = graph(&["a", "b"], &[("b", "a", "Build")]);
+        apply_timings(
+            &mut g,
+            &[
+                unit("a", "", 0.0, 1.0),
+                unit("b", " build-script", 1.0, 0.1),
+                unit("b", " build-script (run)", 1.1, 0.1),
+                unit("b", "", 1.2, 0.1),
+            ],
+        );
+        assert_eq!(vec!["a", "b"], names(&g.critical_path, &g));
+        assert_eq!(UnitKind::BuildScript, g.critical_units[1].kind);
+    }
+
+    #[test]
+    fn library_waits_only_for_dependency_metadata() {
+        // b starts once a's metadata is ready and finishes before a's codegen.
+        let mut g = graph(&["a", "b"], &[("b", "a", "Normal")]);
+        apply_timings(
+            &mut g,
+            &[pipelined("a", 0.0, 1.0, 0.2), pipelined("b", 0.2, 0.5, 0.1)],
+        );
+        assert_eq!(vec!["a"], names(&g.critical_path, &g));
+        assert_eq!(
+            Some(200.0.into()),
+            g.nodes.values().find(|n| n.name == "a").unwrap().rmeta_ms
+        );
+    }
+
+    #[test]
+    fn critical_path_times_add_up() {
+        // Only a's metadata phase is on the critical path.
+        let mut g = graph(&["a", "b"], &[("b", "a", "Normal")]);
+        apply_timings(
+            &mut g,
+            &[pipelined("a", 0.0, 1.0, 0.2), pipelined("b", 0.2, 2.0, 0.1)],
+        );
+        let times = critical_path_times(&g);
+        assert_eq!(
+            vec![
+                (fixtures::id("a"), Milliseconds::from(200.0)),
+                (fixtures::id("b"), Milliseconds::from(2000.0)),
+            ],
+            times
+        );
+        assert_eq!(Milliseconds::from(2200.0), g.critical_path_ms);
+    }
+
+    #[test]
+    fn repeated_runs_use_medians() {
+        let mut g = graph(&["a", "b"], &[("b", "a", "Normal")]);
+        let run = |a: f64, b: f64| vec![unit("a", "", 0.0, a), unit("b", "", a, b)];
+        apply_runs(&mut g, &[run(1.0, 1.0), run(3.0, 2.0), run(2.0, 6.0)]);
+
+        let b = &g.nodes[&fixtures::id("b")];
+        assert_eq!(Some(Milliseconds::from(2000.0)), b.duration_ms);
+        let stats = b.duration_stats.unwrap();
+        assert_eq!(Milliseconds::from(2000.0), stats.median);
+        assert_eq!(Milliseconds::from(1000.0), stats.min);
+        assert_eq!(Milliseconds::from(6000.0), stats.max);
+        assert_eq!(Milliseconds::from(3000.0), b.start_stats.unwrap().max);
+        // a's median 2s plus b's median 2s.
+        assert_eq!(Milliseconds::from(4000.0), g.critical_path_ms);
+    }
+
+    #[test]
+    fn binary_waits_for_full_dependency() {
+        let mut g = graph(&["a", "b"], &[("b", "a", "Normal")]);
+        apply_timings(
+            &mut g,
+            &[
+                pipelined("a", 0.0, 1.0, 0.2),
+                unit("b", " bin \"b\"", 1.0, 0.5),
+            ],
+        );
+        assert_eq!(vec!["a", "b"], names(&g.critical_path, &g));
+    }
+
+    #[test]
+    fn dev_dependency_gates_only_tests() {
+        let mut g = graph(&["a", "b"], &[("b", "a", "Development")]);
+        apply_timings(
+            &mut g,
+            &[
+                unit("a", "", 0.0, 2.0),
+                unit("b", "", 0.0, 0.5),
+                unit("b", " (test)", 2.0, 1.0),
+            ],
+        );
+        assert_eq!(vec!["a", "b"], names(&g.critical_path, &g));
+        assert_eq!(Milliseconds::from(3000.0), g.critical_path_ms);
+        // b's library didn't wait for a.
+        let b = &g.nodes[&fixtures::id("b")];
+        assert_eq!(Some(Milliseconds::zero()), b.earliest_start_ms);
+    }
 }
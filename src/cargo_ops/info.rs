@@ -0,0 +1,174 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cargo_ops::build::BuildOptions;
+use crate::cargo_ops::timings::{TimingSummary, UnitTiming};
+use crate::model::{BuildInfo, BuildMode};
+
+/// Describe how the local build is configured. Toolchain details are filled
+/// in separately by [`add_toolchain`].
+pub fn collect(opts: &BuildOptions) -> BuildInfo {
+    let workspace_dir = workspace_dir(&opts.manifest_path);
+    let git_commit = run(workspace_dir, "git", &["rev-parse", "HEAD"]);
+    let git_dirty = git_commit.is_some()
+        && run(workspace_dir, "git", &["status", "--porcelain"]).is_some_and(|s| !s.is_empty());
+
+    BuildInfo {
+        mode: opts.mode,
+        profile: opts.profile.clone(),
+        features: opts.features.clone(),
+        all_features: opts.all_features,
+        no_default_features: opts.no_default_features,
+        targets: opts.targets.clone(),
+        cargo_args: opts.cargo_args.clone(),
+        jobs: parse_jobs(&opts.cargo_args).or_else(|| {
+            std::env::var("CARGO_BUILD_JOBS")
+                .ok()
+                .and_then(|j| j.parse().ok())
+        }),
+        available_cpus: std::thread::available_parallelism().map_or(0, |n| n.get()),
+        rustflags: rustflags(),
+        rustc_version: None,
+        cargo_version: None,
+        host: None,
+        goodtimes_version: env!("CARGO_PKG_VERSION").to_string(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        git_commit,
+        git_dirty,
+        timings_source: None,
+        runs: 1,
+    }
+}
+
+/// Describe a build imported from a cargo timing report by what the report
+/// records. The local machine, workspace and command line say nothing about
+/// it, so everything else is left unset.
+pub fn imported(timing_html: &Path, summary: TimingSummary, units: &[UnitTiming]) -> BuildInfo {
+    BuildInfo {
+        mode: imported_mode(units),
+        profile: summary.profile.unwrap_or_default(),
+        jobs: summary.jobs,
+        available_cpus: summary.cpus.unwrap_or(0),
+        rustc_version: summary.rustc_version,
+        host: summary.host,
+        goodtimes_version: env!("CARGO_PKG_VERSION").to_string(),
+        timestamp: summary.started_at.unwrap_or(0),
+        timings_source: Some(timing_html.display().to_string()),
+        runs: 1,
+        ..Default::default()
+    }
+}
+
+/// The cargo command that most likely produced the units, judging by their
+/// compile modes. `cargo clippy` can't be told apart from `cargo check`.
+fn imported_mode(units: &[UnitTiming]) -> BuildMode {
+    let has = |modes: &[&str]| units.iter().any(|u| modes.contains(&u.mode()));
+    if has(&["bench"]) {
+        BuildMode::Bench
+    } else if has(&["test"]) {
+        BuildMode::Test
+    } else if has(&["check", "check-test"]) {
+        BuildMode::Check
+    } else if has(&["doc"]) {
+        BuildMode::Doc
+    } else {
+        BuildMode::Build
+    }
+}
+
+/// Record the toolchain the workspace builds with (honouring rustup overrides).
+pub fn add_toolchain(info: &mut BuildInfo, manifest_path: &str) {
+    let workspace_dir = workspace_dir(manifest_path);
+    info.rustc_version = run(workspace_dir, "rustc", &["-vV"]);
+    info.cargo_version = run(workspace_dir, "cargo", &["-V"]);
+    info.host = info.rustc_version.as_deref().and_then(|v| {
+        v.lines()
+            .find_map(|line| line.strip_prefix("host: "))
+            .map(str::to_string)
+    });
+}
+
+pub(crate) fn workspace_dir(manifest_path: &str) -> &Path {
+    Path::new(manifest_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// Run a command and return its trimmed stdout, or `None` if it failed.
+fn run(dir: &Path, program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `RUSTFLAGS` as cargo sees them (`CARGO_ENCODED_RUSTFLAGS` takes precedence).
+fn rustflags() -> Option<String> {
+    if let Ok(encoded) = std::env::var("CARGO_ENCODED_RUSTFLAGS") {
+        return Some(encoded.split('\x1f').collect::<Vec<_>>().join(" "));
+    }
+    std::env::var("RUSTFLAGS").ok()
+}
+
+/// Find `-j N`, `-jN`, `--jobs N` or `--jobs=N` among pass-through cargo args.
+fn parse_jobs(cargo_args: &[String]) -> Option<i32> {
+    let mut args = cargo_args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "-j" | "--jobs" => args.next().map(String::as_str),
+            _ => arg
+                .strip_prefix("--jobs=")
+                .or_else(|| arg.strip_prefix("-j")),
+        };
+        if let Some(jobs) = value.and_then(|v| v.parse().ok()) {
+            return Some(jobs);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn mode_of_imported_units() {
+        let units = |targets: &[&str]| -> Vec<UnitTiming> {
+            targets
+                .iter()
+                .map(|target| crate::model::fixtures::unit("a", target, 0.0, 1.0))
+                .collect()
+        };
+        assert_eq!(BuildMode::Check, imported_mode(&units(&[" (check)"])));
+        assert_eq!(
+            BuildMode::Test,
+            imported_mode(&units(&["", " build-script (run)", " (test)"]))
+        );
+        assert_eq!(
+            BuildMode::Build,
+            imported_mode(&units(&["", " bin \"app\""]))
+        );
+    }
+
+    #[test]
+    fn jobs_from_cargo_args() {
+        assert_eq!(Some(4), parse_jobs(&args(&["--locked", "-j", "4"])));
+        assert_eq!(Some(8), parse_jobs(&args(&["-j8"])));
+        assert_eq!(Some(2), parse_jobs(&args(&["--jobs", "2"])));
+        assert_eq!(Some(3), parse_jobs(&args(&["--jobs=3"])));
+        assert_eq!(Some(-1), parse_jobs(&args(&["-j", "-1"])));
+        assert_eq!(None, parse_jobs(&args(&["--offline"])));
+    }
+}
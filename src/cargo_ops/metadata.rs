@@ -1,13 +1,34 @@
-use cargo_metadata::{MetadataCommand, PackageId};
+use cargo_metadata::{CargoOpt, DependencyKind, Metadata, MetadataCommand, Package, PackageId};
 use std::collections::{HashMap, HashSet};
 
-use crate::model::{BuildGraph, CrateId, CrateNode, DepEdge};
+use crate::cargo_ops::build::{BuildOptions, PackageSelection};
+use crate::model::{BuildGraph, BuildInfo, CrateId, CrateNode, DepEdge, Milliseconds};
 
 pub fn load_dependency_graph(
-    manifest_path: &str,
+    opts: &BuildOptions,
     include_deps: bool,
 ) -> anyhow::Result<BuildGraph> {
-    let metadata = MetadataCommand::new().manifest_path(manifest_path).exec()?;
+    // Leave out platform-specific dependencies that aren't built for the targets.
+    let filter_platform: Vec<String> = opts
+        .targets
+        .iter()
+        .flat_map(|t| ["--filter-platform".to_string(), t.clone()])
+        .collect();
+    let mut cmd = MetadataCommand::new();
+    cmd.manifest_path(&opts.manifest_path)
+        .other_options(filter_platform);
+
+    // Resolve with the same feature flags as the build, so node features and
+    // optional dependency edges match what is actually compiled.
+    if opts.no_default_features {
+        cmd.features(CargoOpt::NoDefaultFeatures);
+    }
+    if opts.all_features {
+        cmd.features(CargoOpt::AllFeatures);
+    } else if !opts.features.is_empty() {
+        cmd.features(CargoOpt::SomeFeatures(opts.features.clone()));
+    }
+    let metadata = cmd.exec()?;
 
     let resolve = metadata
         .resolve
@@ -26,13 +47,46 @@ pub fn load_dependency_graph(
     };
 
     let ws_members: HashSet<&PackageId> = metadata.workspace_members.iter().collect();
+    let selected = selected_packages(&metadata, &opts.selection)?;
+    // Dev-dependencies are only built for the selected packages' tests,
+    // benches and examples.
+    let with_dev_deps: HashSet<&PackageId> = if opts.mode.compiles_dev_targets() {
+        selected.iter().copied().collect()
+    } else {
+        HashSet::new()
+    };
+
+    // Only keep crates the selected packages (transitively) depend on.
+    let resolve_map: HashMap<&PackageId, &cargo_metadata::Node> =
+        resolve.nodes.iter().map(|n| (&n.id, n)).collect();
+    let mut reachable: HashSet<&PackageId> = HashSet::new();
+    let mut queue = selected.clone();
+    while let Some(id) = queue.pop() {
+        if !reachable.insert(id) {
+            continue;
+        }
+        let Some(node) = resolve_map.get(id) else {
+            continue;
+        };
+        for dep in &node.deps {
+            let is_dev_only = dep
+                .dep_kinds
+                .iter()
+                .all(|dk| dk.kind == DependencyKind::Development);
+            if (!is_dev_only || with_dev_deps.contains(id))
+                && (include_deps || ws_members.contains(&dep.pkg))
+            {
+                queue.push(&dep.pkg);
+            }
+        }
+    }
 
     let mut nodes = HashMap::new();
     let mut edges = Vec::new();
 
     for node in &resolve.nodes {
         let is_ws = ws_members.contains(&node.id);
-        if !include_deps && !is_ws {
+        if !reachable.contains(&node.id) {
             continue;
         }
         let Some(pkg) = pkg_map.get(&node.id) else {
@@ -51,50 +105,246 @@ pub fn load_dependency_graph(
                 start_ms: None,
                 fresh: false,
                 features: node.features.clone(),
+                is_proc_macro: pkg.targets.iter().any(|t| t.is_proc_macro()),
+                rmeta_ms: None,
+                units: Vec::new(),
+                start_stats: None,
+                duration_stats: None,
+                earliest_start_ms: None,
+                latest_start_ms: None,
+                slack_ms: None,
             },
         );
 
         for dep in &node.deps {
-            let dep_included = include_deps || ws_members.contains(&dep.pkg);
-            if !dep_included {
+            if !reachable.contains(&dep.pkg) {
                 continue;
             }
-            let dep_kinds: Vec<String> = dep
-                .dep_kinds
-                .iter()
-                .map(|dk| format!("{:?}", dk.kind))
-                .collect();
-            // Skip pure dev-dependency edges — they don't affect compilation
-            // order and can introduce cycles.
-            if dep_kinds.iter().all(|k| k == "Development") {
-                continue;
-            }
-            edges.push(DepEdge {
+            let edge = DepEdge {
                 from: crate_id.clone(),
                 to: short_id(&dep.pkg),
-                dep_kinds,
-            });
+                dep_kinds: dep
+                    .dep_kinds
+                    .iter()
+                    .map(|dk| format!("{:?}", dk.kind))
+                    .collect(),
+            };
+            // Skip pure dev-dependency edges unless the crate's tests are
+            // built — they don't affect compilation order otherwise and can
+            // introduce cycles.
+            if edge.is_dev_only() && !with_dev_deps.contains(&node.id) {
+                continue;
+            }
+            edges.push(edge);
         }
     }
 
-    let roots = metadata.workspace_members.iter().map(short_id).collect();
+    let roots = selected.into_iter().map(short_id).collect();
 
     Ok(BuildGraph {
         nodes,
         edges,
         roots,
+        build_info: BuildInfo::default(),
         critical_path: Vec::new(),
+        critical_path_ms: Milliseconds::zero(),
+        critical_units: Vec::new(),
+        longest_chains: Vec::new(),
     })
 }
 
-/// Return the names of all workspace member packages.
-pub fn workspace_package_names(manifest_path: &str) -> anyhow::Result<Vec<String>> {
-    let metadata = MetadataCommand::new()
-        .manifest_path(manifest_path)
-        .no_deps()
-        .exec()?;
+/// Whether a package ID spec (`name` or `name@version`) refers to `pkg`.
+fn matches_spec(pkg: &Package, spec: &str) -> bool {
+    match spec.split_once('@') {
+        Some((name, version)) => pkg.name == name && pkg.version.to_string() == version,
+        None => pkg.name == spec,
+    }
+}
 
-    let names = metadata.packages.iter().map(|p| p.name.clone()).collect();
+/// Packages selected by `-p`, `--workspace`/`--exclude`, `--bin` or `--lib`.
+/// Without any of these, all workspace members are selected.
+fn selected_packages<'a>(
+    metadata: &'a Metadata,
+    selection: &PackageSelection,
+) -> anyhow::Result<Vec<&'a PackageId>> {
+    let selected = select(
+        &metadata.packages,
+        &metadata.workspace_packages(),
+        selection,
+    )?;
+    Ok(selected.into_iter().map(|p| &p.id).collect())
+}
 
-    Ok(names)
+fn select<'a>(
+    packages: &'a [Package],
+    members: &[&'a Package],
+    selection: &PackageSelection,
+) -> anyhow::Result<Vec<&'a Package>> {
+    let candidates: Vec<&Package> = if selection.packages.is_empty() {
+        members
+            .iter()
+            .copied()
+            .filter(|p| !selection.exclude.iter().any(|spec| matches_spec(p, spec)))
+            .collect()
+    } else {
+        // Like cargo, `-p` may name any package in the dependency graph.
+        let mut selected = Vec::new();
+        for spec in &selection.packages {
+            let matching: Vec<_> = packages.iter().filter(|p| matches_spec(p, spec)).collect();
+            match matching[..] {
+                [] => anyhow::bail!("package `{spec}` not found in the dependency graph"),
+                [pkg] => selected.push(pkg),
+                _ => anyhow::bail!("package `{spec}` is ambiguous, use `name@version`"),
+            }
+        }
+        selected
+    };
+    anyhow::ensure!(!candidates.is_empty(), "no workspace packages selected");
+
+    let selected: Vec<_> = candidates
+        .into_iter()
+        .filter(|p| has_selected_targets(p, selection))
+        .collect();
+    anyhow::ensure!(
+        !selected.is_empty(),
+        "none of the selected packages have the targets given by `--bin`/`--lib`"
+    );
+    Ok(selected)
+}
+
+/// Whether `--bin`/`--lib` leave any of the package's targets to build.
+fn has_selected_targets(pkg: &Package, selection: &PackageSelection) -> bool {
+    if selection.bins.is_empty() && !selection.lib {
+        return true;
+    }
+    pkg.targets.iter().any(|t| {
+        (t.is_bin() && selection.bins.contains(&t.name))
+            || (selection.lib
+                && (t.is_lib()
+                    || t.is_rlib()
+                    || t.is_dylib()
+                    || t.is_cdylib()
+                    || t.is_staticlib()
+                    || t.is_proc_macro()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_metadata::{
+        PackageBuilder, TargetBuilder, TargetKind, camino::Utf8PathBuf, semver::Version,
+    };
+
+    fn package(name: &str, targets: &[(&str, TargetKind)]) -> Package {
+        let targets = targets
+            .iter()
+            .map(|(target, kind)| {
+                TargetBuilder::default()
+                    .name(*target)
+                    .kind(vec![kind.clone()])
+                    .src_path(Utf8PathBuf::new())
+                    .build()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        PackageBuilder::new(
+            name,
+            Version::new(0, 1, 0),
+            PackageId {
+                repr: format!("path+file:///work/{name}#0.1.0"),
+            },
+            Utf8PathBuf::new(),
+        )
+        .targets(targets)
+        .build()
+        .unwrap()
+    }
+
+    #[test]
+    fn package_spec_matching() {
+        let pkg = PackageBuilder::new(
+            "my-crate",
+            Version::new(1, 2, 3),
+            PackageId {
+                repr: "path+file:///work/my-crate#1.2.3".to_string(),
+            },
+            Utf8PathBuf::new(),
+        )
+        .build()
+        .unwrap();
+        assert!(matches_spec(&pkg, "my-crate"));
+        assert!(matches_spec(&pkg, "my-crate@1.2.3"));
+        assert!(!matches_spec(&pkg, "my-crate@1.0.0"));
+        assert!(!matches_spec(&pkg, "other"));
+    }
+
+    #[test]
+    fn packages_are_selected_by_target() {
+        let packages = [
+            package("app", &[("app", TargetKind::Bin)]),
+            package("core", &[("core", TargetKind::Lib)]),
+            package("macros", &[("macros", TargetKind::ProcMacro)]),
+            package(
+                "tool",
+                &[("tool", TargetKind::Lib), ("tool", TargetKind::Bin)],
+            ),
+            package("serde", &[("serde", TargetKind::Lib)]),
+        ];
+        let members: Vec<&Package> = packages[..4].iter().collect();
+        let names = |selection: PackageSelection| -> Vec<String> {
+            select(&packages, &members, &selection)
+                .unwrap()
+                .iter()
+                .map(|p| p.name.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            vec!["tool"],
+            names(PackageSelection {
+                workspace: true,
+                bins: vec!["tool".to_string()],
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec!["core", "macros", "tool"],
+            names(PackageSelection {
+                lib: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec!["app", "core", "macros", "tool"],
+            names(PackageSelection {
+                bins: vec!["app".to_string()],
+                lib: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec!["serde"],
+            names(PackageSelection {
+                packages: vec!["serde".to_string()],
+                ..Default::default()
+            })
+        );
+
+        let fails = |selection: PackageSelection| select(&packages, &members, &selection).is_err();
+        assert!(fails(PackageSelection {
+            packages: vec!["missing".to_string()],
+            ..Default::default()
+        }));
+        assert!(fails(PackageSelection {
+            packages: vec!["app".to_string()],
+            lib: true,
+            ..Default::default()
+        }));
+        assert!(fails(PackageSelection {
+            workspace: true,
+            bins: vec!["missing".to_string()],
+            ..Default::default()
+        }));
+    }
 }
@@ -1,2 +1,5 @@
 pub mod build;
+pub mod info;
 pub mod metadata;
+pub mod simulate;
+pub mod timings;
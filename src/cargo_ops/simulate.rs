@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use crate::cargo_ops::build::UnitDag;
+use crate::model::{BuildGraph, BuildInfo, CrateId, Milliseconds};
+
+/// Where a crate lands in a simulated build.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulatedCrate {
+    pub start_ms: Milliseconds,
+    pub finish_ms: Milliseconds,
+}
+
+/// A build replayed with a limited number of jobs.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// Predicted wall time: when the last unit finished.
+    pub total_ms: Milliseconds,
+    /// Span of each crate's units.
+    pub crates: HashMap<CrateId, SimulatedCrate>,
+}
+
+/// A simulation of the measured build, to judge how far its predictions can
+/// be trusted.
+#[derive(Debug, Clone, Copy)]
+pub struct Validation {
+    pub jobs: usize,
+    pub measured_ms: Milliseconds,
+    pub predicted_ms: Milliseconds,
+    /// Mean difference between each crate's predicted and measured start.
+    pub start_error_ms: Milliseconds,
+}
+
+impl Validation {
+    /// How far the prediction is off, relative to the measured wall time.
+    pub fn error_percent(&self) -> Option<f64> {
+        let measured = f64::from(self.measured_ms);
+        (measured > 0.0).then(|| 100.0 * (f64::from(self.predicted_ms) - measured) / measured)
+    }
+}
+
+/// A unit occupying a job slot.
+struct Running {
+    unit: usize,
+    start: f64,
+    /// Whether dependents waiting only for its metadata have been released.
+    rmeta_released: bool,
+}
+
+/// Replay cargo's scheduling of the graph's units with their measured
+/// durations: a unit becomes ready once its dependencies are done (or, for
+/// pipelined libraries, have emitted metadata), and whenever one of the
+/// `jobs` slots is free the ready unit with the most transitive dependents
+/// starts, as cargo prioritizes them.
+pub fn simulate(graph: &BuildGraph, jobs: usize) -> Simulation {
+    let jobs = jobs.max(1);
+    let dag = UnitDag::new(graph);
+    let order = dag.topo_order();
+    let n = dag.units.len();
+
+    // Dependents of each unit and whether they only wait for its metadata.
+    let mut dependents: Vec<Vec<(usize, bool)>> = vec![Vec::new(); n];
+    for (u, deps) in dag.deps.iter().enumerate() {
+        for dep in deps {
+            dependents[dep.unit].push((u, dep.metadata_only));
+        }
+    }
+    // Each unit's transitive dependents as a row of bits, filled in reverse
+    // topological order so that dependents' rows are complete.
+    let words = n.div_ceil(64);
+    let mut transitive = vec![0u64; n * words];
+    for &u in order.iter().rev() {
+        for &(d, _) in &dependents[u] {
+            transitive[u * words + d / 64] |= 1 << (d % 64);
+            for w in 0..words {
+                let bits = transitive[d * words + w];
+                transitive[u * words + w] |= bits;
+            }
+        }
+    }
+    let priority: Vec<u32> = (0..n)
+        .map(|u| {
+            transitive[u * words..(u + 1) * words]
+                .iter()
+                .map(|w| w.count_ones())
+                .sum()
+        })
+        .collect();
+
+    // Units on a cycle never become ready and are left out.
+    let mut pending: Vec<usize> = dag.deps.iter().map(Vec::len).collect();
+    let mut ready: Vec<usize> = order.iter().copied().filter(|&u| pending[u] == 0).collect();
+    let mut running: Vec<Running> = Vec::new();
+    let mut start = vec![0.0; n];
+    let mut finish = vec![0.0; n];
+    let mut time = 0.0;
+
+    let rmeta = |u: usize| {
+        dag.units[u]
+            .rmeta
+            .map(|r| f64::from(r).min(f64::from(dag.units[u].duration)))
+    };
+    let duration = |u: usize| f64::from(dag.units[u].duration);
+
+    while !ready.is_empty() || !running.is_empty() {
+        while running.len() < jobs && !ready.is_empty() {
+            let next = (0..ready.len())
+                .max_by(|&a, &b| {
+                    priority[ready[a]]
+                        .cmp(&priority[ready[b]])
+                        .then(ready[b].cmp(&ready[a]))
+                })
+                .unwrap_or(0);
+            let unit = ready.swap_remove(next);
+            start[unit] = time;
+            running.push(Running {
+                unit,
+                start: time,
+                rmeta_released: false,
+            });
+        }
+
+        // Advance to the next metadata or completion event.
+        time = running
+            .iter()
+            .map(|r| match rmeta(r.unit) {
+                Some(rmeta) if !r.rmeta_released => r.start + rmeta,
+                _ => r.start + duration(r.unit),
+            })
+            .fold(f64::MAX, f64::min);
+
+        let mut release = |u: usize, metadata: bool, ready: &mut Vec<usize>| {
+            for &(d, metadata_only) in &dependents[u] {
+                if metadata_only == metadata {
+                    pending[d] -= 1;
+                    if pending[d] == 0 {
+                        ready.push(d);
+                    }
+                }
+            }
+        };
+        for r in &mut running {
+            if let Some(rmeta) = rmeta(r.unit)
+                && !r.rmeta_released
+                && r.start + rmeta <= time
+            {
+                r.rmeta_released = true;
+                release(r.unit, true, &mut ready);
+            }
+        }
+        running.retain(|r| {
+            let done = r.start + duration(r.unit) <= time;
+            if done {
+                finish[r.unit] = time;
+                // Without metadata timing, metadata is only ready at the end.
+                if rmeta(r.unit).is_none() {
+                    release(r.unit, true, &mut ready);
+                }
+                release(r.unit, false, &mut ready);
+            }
+            !done
+        });
+    }
+
+    let mut crates: HashMap<CrateId, SimulatedCrate> = HashMap::new();
+    for &u in &order {
+        let (s, f) = (start[u].into(), finish[u].into());
+        crates
+            .entry(dag.units[u].unit.id.clone())
+            .and_modify(|c| {
+                if s < c.start_ms {
+                    c.start_ms = s;
+                }
+                if f > c.finish_ms {
+                    c.finish_ms = f;
+                }
+            })
+            .or_insert(SimulatedCrate {
+                start_ms: s,
+                finish_ms: f,
+            });
+    }
+    let total_ms = order.iter().map(|&u| finish[u]).fold(0.0, f64::max).into();
+    Simulation { total_ms, crates }
+}
+
+/// The number of jobs the measured build ran with: `-j`, where a negative
+/// value leaves that many CPUs free as cargo does, or else every CPU. `None`
+/// if that depends on CPUs the report doesn't record.
+pub fn measured_jobs(info: &BuildInfo) -> Option<usize> {
+    let cpus = (info.available_cpus > 0).then_some(info.available_cpus);
+    match info.jobs {
+        Some(jobs) if jobs > 0 => Some(jobs as usize),
+        Some(jobs) => Some(cpus?.saturating_sub(jobs.unsigned_abs() as usize).max(1)),
+        None => cpus,
+    }
+}
+
+/// Simulate the measured build with the jobs it ran with and compare the
+/// predicted wall time to the measured one. `None` if the jobs are unknown.
+pub fn validate(graph: &BuildGraph) -> Option<Validation> {
+    let jobs = measured_jobs(&graph.build_info)?;
+    let simulation = simulate(graph, jobs);
+    let errors: Vec<f64> = graph
+        .nodes
+        .values()
+        .filter_map(|node| {
+            let predicted = simulation.crates.get(&node.id)?;
+            Some((f64::from(predicted.start_ms) - f64::from(node.start_ms?)).abs())
+        })
+        .collect();
+    Some(Validation {
+        jobs,
+        measured_ms: graph.total_ms(),
+        predicted_ms: simulation.total_ms,
+        start_error_ms: (errors.iter().sum::<f64>() / errors.len().max(1) as f64).into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cargo_ops::build::apply_timings;
+    use crate::cargo_ops::timings::UnitTiming;
+    use crate::model::fixtures::{graph, id, timed_graph, unit};
+
+    #[test]
+    fn unlimited_jobs_follow_the_critical_path() {
+        let g = timed_graph(
+            &[("a", 0.0, 2.0), ("b", 0.0, 1.0), ("c", 2.0, 1.0)],
+            &[("c", "a"), ("c", "b")],
+        );
+        let sim = simulate(&g, 8);
+        assert_eq!(g.critical_path_ms, sim.total_ms);
+        assert_eq!(
+            SimulatedCrate {
+                start_ms: 2000.0.into(),
+                finish_ms: 3000.0.into(),
+            },
+            sim.crates[&id("c")]
+        );
+    }
+
+    #[test]
+    fn replays_a_single_job_build() {
+        // Measured with -j1: the crates ran one after another.
+        let mut g = timed_graph(
+            &[("a", 0.0, 2.0), ("b", 2.0, 1.0), ("c", 3.0, 1.0)],
+            &[("c", "a"), ("c", "b")],
+        );
+        g.build_info.jobs = Some(1);
+        g.build_info.available_cpus = 8;
+        let validation = validate(&g).unwrap();
+        assert_eq!(1, validation.jobs);
+        assert_eq!(Milliseconds::from(4000.0), validation.predicted_ms);
+        assert_eq!(Some(0.0), validation.error_percent());
+        assert_eq!(Milliseconds::zero(), validation.start_error_ms);
+        // Two jobs would save the second crate's time.
+        assert_eq!(Milliseconds::from(3000.0), simulate(&g, 2).total_ms);
+    }
+
+    #[test]
+    fn prefers_units_with_more_dependents() {
+        // With one job, `b` goes first because `c` is waiting on it.
+        let g = timed_graph(
+            &[("a", 0.0, 1.0), ("b", 0.0, 1.0), ("c", 1.0, 1.0)],
+            &[("c", "b")],
+        );
+        let sim = simulate(&g, 1);
+        assert_eq!(Milliseconds::zero(), sim.crates[&id("b")].start_ms);
+        assert_eq!(Milliseconds::from(3000.0), sim.total_ms);
+    }
+
+    #[test]
+    fn pipelined_dependents_start_at_metadata() {
+        let mut g = graph(&["a", "b"], &[("b", "a", "Normal")]);
+        apply_timings(
+            &mut g,
+            &[
+                UnitTiming {
+                    rmeta_time: Some(0.5),
+                    ..unit("a", "", 0.0, 2.0)
+                },
+                unit("b", "", 0.5, 1.0),
+            ],
+        );
+        let sim = simulate(&g, 2);
+        assert_eq!(Milliseconds::from(500.0), sim.crates[&id("b")].start_ms);
+        assert_eq!(Milliseconds::from(2000.0), sim.total_ms);
+        // With one job, `b` waits for `a`'s slot.
+        assert_eq!(Milliseconds::from(3000.0), simulate(&g, 1).total_ms);
+    }
+
+    #[test]
+    fn negative_jobs_leave_cpus_free() {
+        let info = |jobs| BuildInfo {
+            jobs,
+            available_cpus: 8,
+            ..Default::default()
+        };
+        assert_eq!(Some(8), measured_jobs(&info(None)));
+        assert_eq!(Some(3), measured_jobs(&info(Some(3))));
+        assert_eq!(Some(6), measured_jobs(&info(Some(-2))));
+        assert_eq!(Some(1), measured_jobs(&info(Some(-20))));
+        // An imported report without its CPU count.
+        let unknown = |jobs| BuildInfo {
+            jobs,
+            ..Default::default()
+        };
+        assert_eq!(Some(3), measured_jobs(&unknown(Some(3))));
+        assert_eq!(None, measured_jobs(&unknown(Some(-2))));
+        assert_eq!(None, measured_jobs(&unknown(None)));
+    }
+}
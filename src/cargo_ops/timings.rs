@@ -0,0 +1,548 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::model::UnitKind;
+
+/// Per-unit timing, either scraped from cargo's --timings HTML or built from
+/// the nightly `timing-info` JSON messages.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnitTiming {
+    pub name: String,
+    pub version: String,
+    pub target: String,
+    pub start: f64,    // seconds from build start
+    pub duration: f64, // seconds
+    /// Seconds after `start` at which the unit's metadata was ready, if cargo
+    /// reported it (pipelined library builds).
+    #[serde(default)]
+    pub rmeta_time: Option<f64>,
+    /// Target triple the unit was compiled for, when building for explicit
+    /// `--target`s. `None` for host units and when unknown.
+    #[serde(skip)]
+    pub platform: Option<String>,
+}
+
+/// A `compiler-artifact` message, used to tell which platform a unit was
+/// compiled for.
+#[derive(Debug, Clone)]
+pub struct UnitArtifact {
+    pub name: String,
+    pub version: String,
+    pub target: String,
+    pub platform: Option<String>,
+    /// Seconds since the build began at which the artifact was reported.
+    pub finished_at: f64,
+}
+
+impl UnitArtifact {
+    pub fn new(
+        artifact: &cargo_metadata::Artifact,
+        targets: &[String],
+        finished_at: f64,
+    ) -> Option<Self> {
+        let (name, version) = parse_package_id(&artifact.package_id.repr)?;
+        // Target artifacts live under `<target-dir>/<triple>/`, host ones don't.
+        let platform = targets
+            .iter()
+            .find(|triple| {
+                artifact
+                    .filenames
+                    .iter()
+                    .any(|f| f.components().any(|c| c.as_str() == triple.as_str()))
+            })
+            .cloned();
+        Some(UnitArtifact {
+            name,
+            version,
+            target: artifact.target.name.clone(),
+            platform,
+            finished_at,
+        })
+    }
+}
+
+/// Attribute units to platforms by pairing each with the artifact of the same
+/// target that finished closest to it.
+pub fn assign_platforms(units: &mut [UnitTiming], artifacts: &[UnitArtifact]) {
+    let mut used = vec![false; artifacts.len()];
+    for unit in units.iter_mut() {
+        let (kind, target) = unit.kind();
+        if kind == UnitKind::BuildScriptRun {
+            continue;
+        }
+        let target = target.replace('-', "_");
+        let end = unit.start + unit.duration;
+        let closest = artifacts
+            .iter()
+            .enumerate()
+            .filter(|(i, a)| {
+                !used[*i]
+                    && a.name == unit.name
+                    && a.version == unit.version
+                    && a.target.replace('-', "_") == target
+            })
+            .min_by(|(_, a), (_, b)| {
+                (a.finished_at - end)
+                    .abs()
+                    .total_cmp(&(b.finished_at - end).abs())
+            });
+        if let Some((i, artifact)) = closest {
+            used[i] = true;
+            unit.platform = artifact.platform.clone();
+        }
+    }
+}
+
+impl UnitTiming {
+    /// Classify the unit from cargo's target description, e.g. `""`,
+    /// `" (check)"`, `" build-script (run)"` or `" bin \"app\" (test)"`, and
+    /// return its kind along with the target name.
+    pub fn kind(&self) -> (UnitKind, String) {
+        let (target, mode) = self.split_mode();
+
+        // Older cargo versions write "build script", newer ones "build-script".
+        if target == "build-script" || target == "build script" {
+            let kind = if mode == "run" {
+                UnitKind::BuildScriptRun
+            } else {
+                UnitKind::BuildScript
+            };
+            return (kind, "build-script-build".to_string());
+        }
+
+        let (target_kind, name) = match target.split_once(' ') {
+            Some((kind, name)) => (kind, name.trim_matches('"').to_string()),
+            None => (target, self.name.clone()),
+        };
+        let kind = match (mode, target_kind) {
+            ("test" | "check-test", _) => UnitKind::Test,
+            ("bench", _) => UnitKind::Bench,
+            ("doc" | "doc test" | "doc scrape", _) => UnitKind::Doc,
+            (_, "bin") => UnitKind::Bin,
+            (_, "test") => UnitKind::Test,
+            (_, "bench") => UnitKind::Bench,
+            (_, "example") => UnitKind::Example,
+            _ => UnitKind::Lib,
+        };
+        (kind, name)
+    }
+
+    /// Cargo's compile mode from the target description, e.g. `check`, `test`
+    /// or `run`; empty for a plain build.
+    pub fn mode(&self) -> &str {
+        self.split_mode().1
+    }
+
+    fn split_mode(&self) -> (&str, &str) {
+        let desc = self.target.trim();
+        match desc.rfind(" (").or_else(|| desc.find('(')) {
+            Some(idx) if desc.ends_with(')') => (
+                desc[..idx].trim(),
+                desc[idx..].trim().trim_matches(['(', ')']),
+            ),
+            _ => (desc, ""),
+        }
+    }
+}
+
+/// A `timing-info` message, emitted by `cargo -Zunstable-options --timings=json`.
+#[derive(Debug, Deserialize)]
+struct TimingInfo {
+    reason: String,
+    package_id: String,
+    target: TimingTarget,
+    mode: String,
+    duration: f64,
+    rmeta_time: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimingTarget {
+    name: String,
+    kind: Vec<String>,
+}
+
+/// Read the timing report written by `cargo --timings`.
+pub fn load_timing_html(timing_html: &Path) -> anyhow::Result<Vec<UnitTiming>> {
+    let html = std::fs::read_to_string(timing_html)?;
+    parse_unit_data(&html)
+}
+
+/// What the summary table of a cargo timing report records about the build.
+#[derive(Debug, Default, PartialEq)]
+pub struct TimingSummary {
+    pub profile: Option<String>,
+    /// Parallel jobs the build ran with.
+    pub jobs: Option<i32>,
+    /// CPUs available to the build.
+    pub cpus: Option<usize>,
+    /// When the build started, in seconds since the Unix epoch.
+    pub started_at: Option<u64>,
+    /// The `rustc -V` line.
+    pub rustc_version: Option<String>,
+    pub host: Option<String>,
+}
+
+/// Read the summary table of the timing report written by `cargo --timings`.
+pub fn load_timing_summary(timing_html: &Path) -> anyhow::Result<TimingSummary> {
+    let html = std::fs::read_to_string(timing_html)?;
+    Ok(parse_summary(&html))
+}
+
+/// Parse rows like `<td>Profile:</td><td>dev</td>` from the summary table.
+/// Rows that are missing or not understood are left out.
+fn parse_summary(html: &str) -> TimingSummary {
+    let mut summary = TimingSummary::default();
+    let Some(start) = html.find("summary-table") else {
+        return summary;
+    };
+    let table = &html[start..];
+    let table = &table[..table.find("</table>").unwrap_or(table.len())];
+    for row in table.split("<tr>").skip(1) {
+        let cells: Vec<&str> = row
+            .split("<td>")
+            .skip(1)
+            .map(|cell| cell.split("</td>").next().unwrap_or_default().trim())
+            .collect();
+        let [label, value] = cells[..] else {
+            continue;
+        };
+        match label {
+            "Profile:" => summary.profile = Some(value.to_string()),
+            // `4 (jobs=8 ncpu=8)`
+            "Max concurrency:" => {
+                for part in value.split(['(', ')', ' ']) {
+                    if let Some(jobs) = part.strip_prefix("jobs=") {
+                        summary.jobs = jobs.parse().ok();
+                    } else if let Some(cpus) = part.strip_prefix("ncpu=") {
+                        summary.cpus = cpus.parse().ok();
+                    }
+                }
+            }
+            "Build start:" => summary.started_at = parse_utc_timestamp(value),
+            // `rustc 1.95.0 (…)<br>Host: x86_64-unknown-linux-gnu<br>Target: …`
+            "rustc:" => {
+                let mut lines = value.split("<br>").map(str::trim);
+                summary.rustc_version = lines.next().map(str::to_string);
+                summary.host = lines.find_map(|l| l.strip_prefix("Host: ").map(str::to_string));
+            }
+            _ => {}
+        }
+    }
+    summary
+}
+
+/// Seconds since the Unix epoch for a UTC RFC 3339 time such as
+/// `2025-10-09T08:53:20.123Z`.
+fn parse_utc_timestamp(value: &str) -> Option<u64> {
+    let (date, time) = value.strip_suffix('Z')?.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let mut time = time.splitn(3, ':');
+    let hours: u64 = time.next()?.parse().ok()?;
+    let minutes: u64 = time.next()?.parse().ok()?;
+    let seconds: f64 = time.next()?.parse().ok()?;
+    // Days since 1970-01-01 from a civil date (Howard Hinnant's algorithm).
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = u64::try_from(era * 146_097 + doe - 719_468).ok()?;
+    Some(days * 86_400 + hours * 3600 + minutes * 60 + seconds as u64)
+}
+
+/// Extract UNIT_DATA JSON array from the cargo-timing HTML.
+fn parse_unit_data(html: &str) -> anyhow::Result<Vec<UnitTiming>> {
+    // The HTML contains: const UNIT_DATA = [{...}, ...];
+    let start_marker = "const UNIT_DATA = ";
+    let start_idx = html
+        .find(start_marker)
+        .ok_or_else(|| anyhow::anyhow!("UNIT_DATA not found in timing HTML"))?;
+    let rest = &html[start_idx + start_marker.len()..];
+
+    // Let the JSON parser find the end of the array rather than searching for
+    // `];`, which may also appear inside string values.
+    let units = serde_json::Deserializer::from_str(rest)
+        .into_iter::<Vec<UnitTiming>>()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("UNIT_DATA end not found"))??;
+    Ok(units)
+}
+
+/// Parse a `timing-info` message from a single line of cargo's JSON output.
+///
+/// Cargo reports a unit's timing once it has finished, without a start time,
+/// so the start is derived from `finished_at` (seconds since the build began).
+pub fn parse_timing_info(line: &str, finished_at: f64) -> Option<UnitTiming> {
+    let info: TimingInfo = serde_json::from_str(line).ok()?;
+    if info.reason != "timing-info" {
+        return None;
+    }
+    let (name, version) = parse_package_id(&info.package_id)?;
+    Some(UnitTiming {
+        name,
+        version,
+        target: describe_target(&info.target, &info.mode),
+        start: (finished_at - info.duration).max(0.0),
+        duration: info.duration,
+        rmeta_time: info.rmeta_time,
+        platform: None,
+    })
+}
+
+/// Mirror the target description cargo uses in the HTML report, so both
+/// sources are interpreted the same way.
+fn describe_target(target: &TimingTarget, mode: &str) -> String {
+    let kind = |k: &str| target.kind.iter().any(|t| t == k);
+    let mut desc = if kind("custom-build") {
+        " build-script".to_string()
+    } else if kind("bin") {
+        format!(" bin \"{}\"", target.name)
+    } else if kind("test") {
+        format!(" test \"{}\"", target.name)
+    } else if kind("bench") {
+        format!(" bench \"{}\"", target.name)
+    } else if kind("example") {
+        format!(" example \"{}\"", target.name)
+    } else {
+        String::new()
+    };
+    match mode {
+        "run-custom-build" => desc.push_str(" (run)"),
+        "check" => desc.push_str(" (check)"),
+        "test" => desc.push_str(" (test)"),
+        "doc" => desc.push_str(" (doc)"),
+        _ => {}
+    }
+    desc
+}
+
+/// Extract `(name, version)` from a package ID in any of the formats cargo has
+/// used: `name 1.0.0 (source)` before 1.77, and the package ID spec
+/// `source#name@1.0.0` (or `source#1.0.0` when the name matches the last path
+/// segment) since.
+fn parse_package_id(repr: &str) -> Option<(String, String)> {
+    if let Some((source, fragment)) = repr.rsplit_once('#') {
+        if let Some((name, version)) = fragment.split_once('@') {
+            return Some((name.to_string(), version.to_string()));
+        }
+        let path = source.split(['?', '#']).next()?.trim_end_matches('/');
+        let name = path.rsplit('/').next()?;
+        return Some((name.to_string(), fragment.to_string()));
+    }
+    let mut parts = repr.split_whitespace();
+    let name = parts.next()?;
+    let version = parts.next()?;
+    Some((name.to_string(), version.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// UNIT_DATA as written by cargo 1.60–1.78 (with `rmeta_time`).
+    const HTML_1_70: &str = r#"<script>
+const UNIT_DATA = [
+  {"i": 0, "name": "serde", "version": "1.0.160", "mode": "todo", "target": "",
+   "start": 0.10, "duration": 2.50, "rmeta_time": 1.20,
+   "unblocked_units": [1], "unblocked_rmeta_units": []},
+  {"i": 1, "name": "app", "version": "0.1.0", "mode": "todo", "target": " bin \"app\"",
+   "start": 2.60, "duration": 0.40,
+   "unblocked_units": [], "unblocked_rmeta_units": []}
+];
+const CONCURRENCY_DATA = [];
+</script>"#;
+
+    /// UNIT_DATA as written by cargo 1.85+ (with `sections`, no `rmeta_time`).
+    const HTML_1_95: &str = r#"<script>
+const UNIT_DATA = [
+  {"i": 2, "name": "b", "version": "0.1.0", "mode": "todo", "target": " build-script",
+   "features": [], "start": 0.02, "duration": 0.08,
+   "unblocked_units": [3], "unblocked_rmeta_units": [], "sections": null},
+  {"i": 3, "name": "b", "version": "0.1.0", "mode": "run-custom-build",
+   "target": " build-script (run)", "features": [], "start": 0.11, "duration": 0.0,
+   "unblocked_units": [], "unblocked_rmeta_units": [], "sections": null},
+  {"i": 0, "name": "we];ird", "version": "0.1.0", "mode": "todo", "target": " (check)",
+   "features": [], "start": 0.11, "duration": 0.03,
+   "unblocked_units": [], "unblocked_rmeta_units": [],
+   "sections": [["frontend", {"start": 0.0, "end": 0.02}]]}
+];
+const CONCURRENCY_DATA = [];
+</script>"#;
+
+    #[test]
+    fn parse_unit_data_with_rmeta_time() {
+        let units = parse_unit_data(HTML_1_70).unwrap();
+        assert_eq!(2, units.len());
+        assert_eq!("serde", units[0].name);
+        assert_eq!(2.5, units[0].duration);
+        assert_eq!(Some(1.2), units[0].rmeta_time);
+        assert_eq!(None, units[1].rmeta_time);
+        assert_eq!(" bin \"app\"", units[1].target);
+    }
+
+    #[test]
+    fn parse_unit_data_with_sections() {
+        let units = parse_unit_data(HTML_1_95).unwrap();
+        assert_eq!(3, units.len());
+        assert_eq!(" build-script (run)", units[1].target);
+        assert_eq!("we];ird", units[2].name);
+    }
+
+    #[test]
+    fn summary_table() {
+        let html = r#"<table class="my-table summary-table">
+<tr>
+<td>Profile:</td><td>release</td>
+</tr>
+<tr>
+<td>Max concurrency:</td><td>6 (jobs=8 ncpu=16)</td>
+</tr>
+<tr>
+<td>Build start:</td><td>2025-10-09T08:53:20.996231561Z</td>
+</tr>
+<tr>
+<td>rustc:</td><td>rustc 1.95.0 (59807616e 2026-04-14)<br>Host: x86_64-unknown-linux-gnu<br>Target: x86_64-unknown-linux-gnu</td>
+</tr>
+</table>"#;
+        assert_eq!(
+            TimingSummary {
+                profile: Some("release".to_string()),
+                jobs: Some(8),
+                cpus: Some(16),
+                started_at: Some(1_760_000_000),
+                rustc_version: Some("rustc 1.95.0 (59807616e 2026-04-14)".to_string()),
+                host: Some("x86_64-unknown-linux-gnu".to_string()),
+            },
+            parse_summary(html)
+        );
+        assert_eq!(TimingSummary::default(), parse_summary(HTML_1_70));
+        assert_eq!(
+            Some(951_868_799),
+            parse_utc_timestamp("2000-02-29T23:59:59Z")
+        );
+    }
+
+    fn kind_of(target: &str) -> (UnitKind, String) {
+        UnitTiming {
+            name: "krate".to_string(),
+            version: "0.1.0".to_string(),
+            target: target.to_string(),
+            start: 0.0,
+            duration: 0.0,
+            rmeta_time: None,
+            platform: None,
+        }
+        .kind()
+    }
+
+    #[test]
+    fn unit_kinds() {
+        assert_eq!((UnitKind::Lib, "krate".into()), kind_of(""));
+        assert_eq!((UnitKind::Lib, "krate".into()), kind_of(" (check)"));
+        assert_eq!((UnitKind::Lib, "krate".into()), kind_of(" lib"));
+        assert_eq!(
+            (UnitKind::BuildScript, "build-script-build".into()),
+            kind_of(" build-script")
+        );
+        assert_eq!(
+            (UnitKind::BuildScriptRun, "build-script-build".into()),
+            kind_of(" build script (run)")
+        );
+        assert_eq!((UnitKind::Bin, "app".into()), kind_of(" bin \"app\""));
+        assert_eq!(
+            (UnitKind::Bin, "app".into()),
+            kind_of(" bin \"app\" (check)")
+        );
+        assert_eq!(
+            (UnitKind::Test, "app".into()),
+            kind_of(" bin \"app\" (test)")
+        );
+        assert_eq!((UnitKind::Test, "krate".into()), kind_of(" (test)"));
+        assert_eq!((UnitKind::Doc, "krate".into()), kind_of(" (doc)"));
+    }
+
+    #[test]
+    fn parse_unit_data_missing() {
+        assert!(parse_unit_data("<html></html>").is_err());
+    }
+
+    #[test]
+    fn parse_timing_info_lib() {
+        let line = r#"{"reason":"timing-info","package_id":"registry+https://github.com/rust-lang/crates.io-index#serde@1.0.210","target":{"kind":["lib"],"crate_types":["lib"],"name":"serde","src_path":"/src/lib.rs","edition":"2018","doc":true,"doctest":true,"test":true},"mode":"check","duration":1.5,"rmeta_time":0.9}"#;
+        let unit = parse_timing_info(line, 4.0).unwrap();
+        assert_eq!("serde", unit.name);
+        assert_eq!("1.0.210", unit.version);
+        assert_eq!(" (check)", unit.target);
+        assert_eq!(2.5, unit.start);
+        assert_eq!(Some(0.9), unit.rmeta_time);
+    }
+
+    #[test]
+    fn parse_timing_info_build_script_run() {
+        let line = r#"{"reason":"timing-info","package_id":"path+file:///work/b#0.1.0","target":{"kind":["custom-build"],"crate_types":["bin"],"name":"build-script-build","src_path":"/work/b/build.rs","edition":"2024","doc":false,"doctest":false,"test":false},"mode":"run-custom-build","duration":0.2}"#;
+        let unit = parse_timing_info(line, 0.1).unwrap();
+        assert_eq!("b", unit.name);
+        assert_eq!(" build-script (run)", unit.target);
+        assert_eq!(0.0, unit.start);
+    }
+
+    #[test]
+    fn parse_timing_info_ignores_other_messages() {
+        let line = r#"{"reason":"build-finished","success":true}"#;
+        assert!(parse_timing_info(line, 1.0).is_none());
+    }
+
+    #[test]
+    fn package_id_formats() {
+        // cargo < 1.77
+        assert_eq!(
+            Some(("serde".into(), "1.0.160".into())),
+            parse_package_id(
+                "serde 1.0.160 (registry+https://github.com/rust-lang/crates.io-index)"
+            )
+        );
+        // cargo >= 1.77
+        assert_eq!(
+            Some(("my-crate".into(), "0.2.0".into())),
+            parse_package_id("path+file:///work/crates/other#my-crate@0.2.0")
+        );
+        assert_eq!(
+            Some(("other".into(), "0.2.0".into())),
+            parse_package_id("path+file:///work/crates/other#0.2.0")
+        );
+    }
+
+    #[test]
+    fn platforms_follow_closest_artifact() {
+        let lib = |start: f64| UnitTiming {
+            name: "my-lib".to_string(),
+            version: "1.0.0".to_string(),
+            target: String::new(),
+            start,
+            duration: 1.0,
+            rmeta_time: None,
+            platform: None,
+        };
+        let artifact = |platform: Option<&str>, finished_at: f64| UnitArtifact {
+            name: "my-lib".to_string(),
+            version: "1.0.0".to_string(),
+            target: "my_lib".to_string(),
+            platform: platform.map(str::to_string),
+            finished_at,
+        };
+        let mut units = vec![lib(0.0), lib(2.0)];
+        assign_platforms(
+            &mut units,
+            &[
+                artifact(Some("wasm32-unknown-unknown"), 3.1),
+                artifact(None, 1.05),
+            ],
+        );
+        assert_eq!(None, units[0].platform);
+        assert_eq!(Some("wasm32-unknown-unknown"), units[1].platform.as_deref());
+    }
+}
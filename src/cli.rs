@@ -1,5 +1,12 @@
+use std::path::PathBuf;
+
 use clap::Parser;
 
+use crate::diff::{Gate, Threshold};
+use crate::model::BuildMode;
+use crate::output::OutputFormat;
+use crate::whatif::{DurationOverride, DurationSpec, EdgeSpec};
+
 #[derive(Parser)]
 #[command(name = "cargo", bin_name = "cargo")]
 pub enum Cargo {
@@ -8,28 +15,342 @@ pub enum Cargo {
 
 #[derive(clap::Args, Debug)]
 #[command(version, about = "Interactive compilation timing analyzer")]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Path to Cargo.toml or directory containing it.
     #[arg(long, default_value = ".")]
     pub manifest_path: String,
 
+    /// Cargo command to time.
+    #[arg(long, value_enum, default_value_t = BuildMode::Check)]
+    pub mode: BuildMode,
+
+    /// Package(s) to time (may be given multiple times).
+    #[arg(short = 'p', long = "package", value_name = "SPEC")]
+    pub packages: Vec<String>,
+
+    /// Time all packages in the workspace.
+    #[arg(long)]
+    pub workspace: bool,
+
+    /// Exclude packages from `--workspace`.
+    #[arg(long, value_name = "SPEC", requires = "workspace")]
+    pub exclude: Vec<String>,
+
+    /// Only the specified binary (may be given multiple times).
+    #[arg(long = "bin", value_name = "NAME")]
+    pub bins: Vec<String>,
+
+    /// Only the package's library.
+    #[arg(long)]
+    pub lib: bool,
+
     /// Build profile.
     #[arg(long, default_value = "dev")]
     pub profile: String,
 
-    /// Features to enable (comma-separated).
-    #[arg(long, value_delimiter = ',')]
-    pub features: Vec<String>,
+    /// Features to enable (comma- or space-separated, `feature` or `package/feature`).
+    #[arg(short = 'F', long, value_parser = parse_features)]
+    pub features: Vec<Vec<String>>,
 
     /// Enable all features.
     #[arg(long)]
     pub all_features: bool,
 
+    /// Don't enable the `default` feature.
+    #[arg(long)]
+    pub no_default_features: bool,
+
+    /// Build for the target triple (may be given multiple times).
+    #[arg(long = "target", value_name = "TRIPLE")]
+    pub targets: Vec<String>,
+
     /// Include third-party dependencies in the chart.
     #[arg(long)]
     pub include_deps: bool,
 
+    /// Repeat the clean and timed build N times and use the median timings.
+    #[arg(long, value_name = "N", default_value_t = 1,
+          value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "from_timings")]
+    pub runs: u32,
+
+    /// Analyze an existing `cargo-timing.html` (or `cargo-timings/` directory)
+    /// instead of running a build.
+    #[arg(long, value_name = "PATH")]
+    pub from_timings: Option<PathBuf>,
+
+    /// Report format.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Html)]
+    pub format: OutputFormat,
+
+    /// Compare the build against a JSON report from an earlier run, writing
+    /// the comparison (html, text or markdown) instead of the report.
+    #[arg(long, value_name = "REPORT")]
+    pub baseline: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub gate: GateArgs,
+
+    /// Where to write the report (`-` for stdout). Defaults to
+    /// `<target-dir>/cargo-goodtimes/`.
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Number of slowest crates listed in text and Markdown summaries.
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    pub top: usize,
+
+    /// List crates whose slack is within this percentage of the critical
+    /// path as near-critical in text and Markdown summaries.
+    #[arg(long, value_name = "PERCENT", default_value_t = 10.0)]
+    pub near_critical: f64,
+
     /// Don't open browser automatically.
     #[arg(long)]
     pub no_open: bool,
+
+    /// Extra arguments passed to cargo, e.g. `-- --locked -j 4`.
+    #[arg(last = true, value_name = "CARGO_ARGS")]
+    pub cargo_args: Vec<String>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Compare two JSON reports written with `--format json`.
+    Diff(DiffArgs),
+    /// List recorded runs and plot total time and critical path over time.
+    History(HistoryArgs),
+    /// Predict the build with dependencies removed or added, or compile
+    /// times changed.
+    WhatIf(WhatIfArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// Report of the earlier build.
+    pub old: PathBuf,
+
+    /// Report of the later build.
+    pub new: PathBuf,
+
+    #[command(flatten)]
+    pub gate: GateArgs,
+
+    /// Comparison format (html, text or markdown).
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Where to write the comparison (`-` for stdout). Defaults to the
+    /// directory of the new report; text goes to stdout.
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Number of most changed crates listed.
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    pub top: usize,
+
+    /// Don't open browser automatically.
+    #[arg(long)]
+    pub no_open: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct HistoryArgs {
+    /// Path to Cargo.toml or directory containing it.
+    #[arg(long, default_value = ".")]
+    pub manifest_path: String,
+
+    /// Only show the N most recent runs.
+    #[arg(long, value_name = "N")]
+    pub last: Option<usize>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct WhatIfArgs {
+    /// JSON report of the build to change. Defaults to the latest recorded run.
+    #[arg(long, value_name = "REPORT")]
+    pub report: Option<PathBuf>,
+
+    /// Path to Cargo.toml or directory containing it, to find recorded runs.
+    #[arg(long, default_value = ".")]
+    pub manifest_path: String,
+
+    /// Remove a dependency, as `dependent~dependency` (may be given multiple times).
+    #[arg(long, value_name = "FROM~TO")]
+    pub remove: Vec<EdgeSpec>,
+
+    /// Add a dependency, as `dependent~dependency` (may be given multiple times).
+    #[arg(long, value_name = "FROM~TO")]
+    pub add: Vec<EdgeSpec>,
+
+    /// Change a crate's compile time, as `crate:0.5x` or `crate:800ms` (may be
+    /// given multiple times).
+    #[arg(long, value_name = "CRATE:TIME")]
+    pub duration: Vec<DurationSpec>,
+
+    /// Rank the workspace crates by how much changing their compile time by
+    /// this (`0.5x`, `800ms`) would save.
+    #[arg(long, value_name = "TIME")]
+    pub rank: Option<DurationOverride>,
+
+    /// Number of crates listed by `--rank`.
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    pub top: usize,
+
+    /// Replay the changes made in the HTML report, from its URL or the
+    /// `rm=…&add=…` part of it.
+    #[arg(long, value_name = "URL")]
+    pub hash: Option<String>,
+
+    /// Jobs to simulate. Defaults to the jobs the measured build ran with;
+    /// required for imported reports that don't record them.
+    #[arg(short, long, value_name = "N")]
+    pub jobs: Option<usize>,
+}
+
+/// Exit with an error when a comparison shows a regression.
+#[derive(clap::Args, Debug)]
+pub struct GateArgs {
+    /// Fail if the total time or any workspace crate got slower by more than
+    /// this (`5%`, `500ms`, `2s`).
+    #[arg(long, value_name = "THRESHOLD")]
+    pub fail_if_slower: Option<Threshold>,
+
+    /// Fail if the critical path got longer (by more than `--fail-if-slower`,
+    /// if given).
+    #[arg(long)]
+    pub fail_if_critical_path_grows: bool,
+}
+
+impl GateArgs {
+    pub fn gate(&self) -> Gate {
+        Gate {
+            slower: self.fail_if_slower,
+            critical_path: self.fail_if_critical_path_grows,
+        }
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.fail_if_slower.is_some() || self.fail_if_critical_path_grows
+    }
+}
+
+/// Split a `--features` value the way cargo does: on commas and whitespace.
+fn parse_features(value: &str) -> Result<Vec<String>, std::convert::Infallible> {
+    Ok(value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        let Cargo::Goodtimes(args) = Cargo::parse_from(["cargo", "goodtimes"].iter().chain(args));
+        args
+    }
+
+    #[test]
+    fn features_accept_cargo_syntax() {
+        let args = parse(&["--features", "a,b c", "-F", "my-crate/d"]);
+        let features: Vec<String> = args.features.into_iter().flatten().collect();
+        assert_eq!(vec!["a", "b", "c", "my-crate/d"], features);
+    }
+
+    #[test]
+    fn trailing_args_are_passed_through() {
+        let args = parse(&[
+            "--mode",
+            "build",
+            "--",
+            "--locked",
+            "-Zbuild-std",
+            "-j",
+            "4",
+        ]);
+        assert_eq!(vec!["--locked", "-Zbuild-std", "-j", "4"], args.cargo_args);
+    }
+
+    #[test]
+    fn test_mode() {
+        let args = parse(&["--mode", "test"]);
+        assert_eq!(BuildMode::Test, args.mode);
+        assert_eq!(["test", "--no-run"], args.mode.cargo_args());
+        assert_eq!(BuildMode::Check, parse(&[]).mode);
+    }
+
+    #[test]
+    fn diff_subcommand() {
+        let args = parse(&["diff", "old.json", "new.json", "--format", "markdown"]);
+        let Some(Command::Diff(diff)) = args.command else {
+            panic!("expected diff subcommand");
+        };
+        assert_eq!(PathBuf::from("old.json"), diff.old);
+        assert_eq!(PathBuf::from("new.json"), diff.new);
+        assert_eq!(OutputFormat::Markdown, diff.format);
+        assert!(parse(&["--mode", "build"]).command.is_none());
+    }
+
+    #[test]
+    fn history_subcommand() {
+        let args = parse(&["history", "--last", "5"]);
+        let Some(Command::History(history)) = args.command else {
+            panic!("expected history subcommand");
+        };
+        assert_eq!(".", history.manifest_path);
+        assert_eq!(Some(5), history.last);
+    }
+
+    #[test]
+    fn what_if_subcommand() {
+        let args = parse(&[
+            "what-if",
+            "--remove",
+            "a@1.0.0~b@1.0.0",
+            "--add",
+            "c~d",
+            "--duration",
+            "e@1.0.0:0.5x",
+            "--rank",
+            "800ms",
+            "-j",
+            "4",
+        ]);
+        let Some(Command::WhatIf(what_if)) = args.command else {
+            panic!("expected what-if subcommand");
+        };
+        assert_eq!("a@1.0.0", what_if.remove[0].from);
+        assert_eq!("d", what_if.add[0].to);
+        assert_eq!("e@1.0.0", what_if.duration[0].krate);
+        assert_eq!(Some(DurationOverride::Millis(800.0)), what_if.rank);
+        assert_eq!(Some(4), what_if.jobs);
+        assert!(Cargo::try_parse_from(["cargo", "goodtimes", "what-if", "--add", "c"]).is_err());
+    }
+
+    #[test]
+    fn regression_gates() {
+        let args = parse(&[
+            "diff",
+            "old.json",
+            "new.json",
+            "--fail-if-slower",
+            "5%",
+            "--fail-if-critical-path-grows",
+        ]);
+        let Some(Command::Diff(diff)) = args.command else {
+            panic!("expected diff subcommand");
+        };
+        let gate = diff.gate.gate();
+        assert_eq!(Some(Threshold::Percent(5.0)), gate.slower);
+        assert!(gate.critical_path);
+
+        let args = parse(&["--baseline", "main.json", "--fail-if-slower", "2s"]);
+        assert_eq!(Some(Threshold::Millis(2000.0)), args.gate.gate().slower);
+    }
 }
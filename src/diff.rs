@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::model::{BuildGraph, CrateId, CrateNode, Milliseconds, parse_duration_ms};
+
+/// A crate present in both builds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrateDelta {
+    pub name: String,
+    pub old_version: String,
+    pub new_version: String,
+    pub is_workspace_member: bool,
+    pub old_ms: Option<Milliseconds>,
+    pub new_ms: Option<Milliseconds>,
+}
+
+impl CrateDelta {
+    /// Change in compile time; `None` unless the crate was timed in both builds.
+    pub fn delta_ms(&self) -> Option<f64> {
+        Some(f64::from(self.new_ms?) - f64::from(self.old_ms?))
+    }
+
+    /// Change relative to the old compile time, in percent.
+    pub fn delta_percent(&self) -> Option<f64> {
+        let old = f64::from(self.old_ms?);
+        let delta = self.delta_ms()?;
+        (old > 0.0).then(|| 100.0 * delta / old)
+    }
+}
+
+/// A crate on a critical path, with its compile time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathEntry {
+    pub name: String,
+    pub version: String,
+    pub duration_ms: Option<Milliseconds>,
+}
+
+/// How one build compares to another.
+#[derive(Debug, Clone)]
+pub struct BuildDiff {
+    pub old_total_ms: Milliseconds,
+    pub new_total_ms: Milliseconds,
+    pub old_critical_path_ms: Milliseconds,
+    pub new_critical_path_ms: Milliseconds,
+    pub old_critical_path: Vec<PathEntry>,
+    pub new_critical_path: Vec<PathEntry>,
+    /// Crates in both builds, largest change first.
+    pub crates: Vec<CrateDelta>,
+    pub added: Vec<CrateId>,
+    pub removed: Vec<CrateId>,
+    /// Dependency edges (dependent, dependency) only in the new build.
+    pub added_edges: Vec<(String, String)>,
+    /// Dependency edges (dependent, dependency) only in the old build.
+    pub removed_edges: Vec<(String, String)>,
+}
+
+impl BuildDiff {
+    pub fn total_delta_ms(&self) -> f64 {
+        f64::from(self.new_total_ms) - f64::from(self.old_total_ms)
+    }
+
+    pub fn critical_path_delta_ms(&self) -> f64 {
+        f64::from(self.new_critical_path_ms) - f64::from(self.old_critical_path_ms)
+    }
+}
+
+/// How much slower a build may get: `5%` or a duration like `500ms` or `2s`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    Percent(f64),
+    Millis(f64),
+}
+
+impl Threshold {
+    /// Whether going from `old` to `new` ms exceeds the threshold.
+    fn exceeded(self, old: f64, new: f64) -> bool {
+        match self {
+            Threshold::Percent(percent) => old > 0.0 && 100.0 * (new - old) / old > percent,
+            Threshold::Millis(ms) => new - old > ms,
+        }
+    }
+}
+
+impl std::str::FromStr for Threshold {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let threshold = match value.strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| *n >= 0.0)
+                .map(Threshold::Percent),
+            None => parse_duration_ms(value).map(Threshold::Millis),
+        };
+        threshold.ok_or_else(|| {
+            format!(
+                "invalid threshold `{value}`: expected a percentage (5%) or a duration (500ms, 2s)"
+            )
+        })
+    }
+}
+
+/// Workspace crates faster than this in both builds are ignored by
+/// percentage thresholds: their timings are mostly noise.
+const MIN_GATED_CRATE_MS: f64 = 100.0;
+
+/// Which regressions fail the run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gate {
+    /// Fail if the total time or any workspace crate got slower than this.
+    pub slower: Option<Threshold>,
+    /// Fail if the critical path got longer: by more than `slower` if set,
+    /// otherwise at all.
+    pub critical_path: bool,
+}
+
+impl Gate {
+    /// Describe every regression that exceeds the gate.
+    pub fn regressions(&self, diff: &BuildDiff) -> Vec<String> {
+        let mut regressions = Vec::new();
+        let describe = |what: &str, old: f64, new: f64| {
+            format!(
+                "{what} went from {:.0}ms to {:.0}ms ({:+.0}ms)",
+                old,
+                new,
+                new - old
+            )
+        };
+
+        if let Some(threshold) = self.slower {
+            let (old, new) = (diff.old_total_ms.into(), diff.new_total_ms.into());
+            if threshold.exceeded(old, new) {
+                regressions.push(describe("total time", old, new));
+            }
+            for delta in diff.crates.iter().filter(|c| c.is_workspace_member) {
+                let (Some(old), Some(new)) = (delta.old_ms, delta.new_ms) else {
+                    continue;
+                };
+                let (old, new) = (f64::from(old), f64::from(new));
+                let too_fast =
+                    matches!(threshold, Threshold::Percent(_)) && old.max(new) < MIN_GATED_CRATE_MS;
+                if !too_fast && threshold.exceeded(old, new) {
+                    regressions.push(describe(&format!("crate `{}`", delta.name), old, new));
+                }
+            }
+        }
+
+        if self.critical_path {
+            let (old, new) = (
+                diff.old_critical_path_ms.into(),
+                diff.new_critical_path_ms.into(),
+            );
+            let threshold = self.slower.unwrap_or(Threshold::Millis(0.0));
+            if threshold.exceeded(old, new) {
+                regressions.push(describe("critical path", old, new));
+            }
+        }
+        regressions
+    }
+}
+
+/// Compare two builds. Crates are matched by ID, then by name, so a version
+/// bump counts as the same crate.
+pub fn diff(old: &BuildGraph, new: &BuildGraph) -> BuildDiff {
+    let matches = match_crates(old, new);
+
+    let mut crates: Vec<CrateDelta> = matches
+        .iter()
+        .map(|(old_id, new_id)| {
+            let (o, n) = (&old.nodes[*old_id], &new.nodes[*new_id]);
+            CrateDelta {
+                name: n.name.clone(),
+                old_version: o.version.clone(),
+                new_version: n.version.clone(),
+                is_workspace_member: n.is_workspace_member,
+                old_ms: o.duration_ms,
+                new_ms: n.duration_ms,
+            }
+        })
+        .collect();
+    crates.sort_by(|a, b| {
+        let size = |d: &CrateDelta| d.delta_ms().map_or(0.0, f64::abs);
+        size(b)
+            .total_cmp(&size(a))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let matched_new: HashSet<&CrateId> = matches.values().copied().collect();
+    let mut added: Vec<CrateId> = new
+        .nodes
+        .keys()
+        .filter(|id| !matched_new.contains(id))
+        .cloned()
+        .collect();
+    added.sort();
+    let mut removed: Vec<CrateId> = old
+        .nodes
+        .keys()
+        .filter(|id| !matches.contains_key(id))
+        .cloned()
+        .collect();
+    removed.sort();
+
+    // Compare edges by crate name, so edges to a bumped crate still match.
+    let edge_names = |graph: &BuildGraph| -> HashSet<(String, String)> {
+        graph
+            .edges
+            .iter()
+            .filter_map(|e| {
+                let from = graph.nodes.get(&e.from)?;
+                let to = graph.nodes.get(&e.to)?;
+                Some((from.name.clone(), to.name.clone()))
+            })
+            .collect()
+    };
+    let (old_edges, new_edges) = (edge_names(old), edge_names(new));
+    let mut added_edges: Vec<_> = new_edges.difference(&old_edges).cloned().collect();
+    added_edges.sort();
+    let mut removed_edges: Vec<_> = old_edges.difference(&new_edges).cloned().collect();
+    removed_edges.sort();
+
+    BuildDiff {
+        old_total_ms: old.total_ms(),
+        new_total_ms: new.total_ms(),
+        old_critical_path_ms: old.critical_path_ms,
+        new_critical_path_ms: new.critical_path_ms,
+        old_critical_path: path_entries(old),
+        new_critical_path: path_entries(new),
+        crates,
+        added,
+        removed,
+        added_edges,
+        removed_edges,
+    }
+}
+
+/// Pair old crate IDs with new ones: by ID, then by name where the name is
+/// unambiguous on both sides.
+fn match_crates<'a>(old: &'a BuildGraph, new: &'a BuildGraph) -> HashMap<&'a CrateId, &'a CrateId> {
+    let mut matches: HashMap<&CrateId, &CrateId> = old
+        .nodes
+        .keys()
+        .filter_map(|id| Some((id, new.nodes.get_key_value(id)?.0)))
+        .collect();
+
+    let unmatched_by_name = |graph: &'a BuildGraph, matched: &HashSet<&CrateId>| {
+        let mut by_name: HashMap<&'a str, Vec<&'a CrateId>> = HashMap::new();
+        for node in graph.nodes.values() {
+            if !matched.contains(&node.id) {
+                by_name.entry(&node.name).or_default().push(&node.id);
+            }
+        }
+        by_name
+    };
+    let matched_old: HashSet<&CrateId> = matches.keys().copied().collect();
+    let matched_new: HashSet<&CrateId> = matches.values().copied().collect();
+    let old_by_name = unmatched_by_name(old, &matched_old);
+    let new_by_name = unmatched_by_name(new, &matched_new);
+    for (name, old_ids) in &old_by_name {
+        if let (&[old_id], Some(&[new_id])) =
+            (old_ids.as_slice(), new_by_name.get(name).map(Vec::as_slice))
+        {
+            matches.insert(old_id, new_id);
+        }
+    }
+    matches
+}
+
+fn path_entries(graph: &BuildGraph) -> Vec<PathEntry> {
+    graph
+        .critical_path
+        .iter()
+        .filter_map(|id| graph.nodes.get(id))
+        .map(|n: &CrateNode| PathEntry {
+            name: n.name.clone(),
+            version: n.version.clone(),
+            duration_ms: n.duration_ms,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::{id, timed_graph};
+
+    #[test]
+    fn crates_match_by_id_then_name() {
+        let old = timed_graph(
+            &[("a", 0.0, 1.0), ("b", 1.0, 1.0), ("gone", 0.0, 0.5)],
+            &[("b", "a")],
+        );
+        let mut new = timed_graph(
+            &[("a", 0.0, 1.0), ("b", 1.0, 3.0), ("new", 0.0, 0.5)],
+            &[("b", "a"), ("b", "new")],
+        );
+        // Bump a's version: still the same crate.
+        let bumped = CrateId::from(&cargo_metadata::PackageId {
+            repr: "a@0.2.0".to_string(),
+        });
+        let mut a = new.nodes.remove(&id("a")).unwrap();
+        a.id = bumped.clone();
+        a.version = "0.2.0".to_string();
+        a.duration_ms = Some(1500.0.into());
+        new.nodes.insert(bumped.clone(), a);
+        for edge in &mut new.edges {
+            if edge.to == id("a") {
+                edge.to = bumped.clone();
+            }
+        }
+
+        let d = diff(&old, &new);
+        assert_eq!(
+            vec![("b", Some(2000.0)), ("a", Some(500.0))],
+            d.crates
+                .iter()
+                .map(|c| (c.name.as_str(), c.delta_ms()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("0.2.0", d.crates[1].new_version);
+        assert_eq!(vec![id("new")], d.added);
+        assert_eq!(vec![id("gone")], d.removed);
+        assert_eq!(vec![("b".to_string(), "new".to_string())], d.added_edges);
+        assert!(d.removed_edges.is_empty());
+        assert_eq!(2000.0, d.critical_path_delta_ms());
+    }
+
+    #[test]
+    fn thresholds_parse() {
+        assert_eq!(Ok(Threshold::Percent(5.0)), "5%".parse());
+        assert_eq!(Ok(Threshold::Millis(500.0)), "500ms".parse());
+        assert_eq!(Ok(Threshold::Millis(2500.0)), "2.5s".parse());
+        assert!("500".parse::<Threshold>().is_err());
+        assert!("-5%".parse::<Threshold>().is_err());
+    }
+
+    #[test]
+    fn gate_reports_regressions_beyond_threshold() {
+        let old = timed_graph(
+            &[("a", 0.0, 1.0), ("b", 1.0, 1.0), ("tiny", 0.0, 0.01)],
+            &[("b", "a")],
+        );
+        let new = timed_graph(
+            &[("a", 0.0, 1.04), ("b", 1.04, 1.5), ("tiny", 0.0, 0.05)],
+            &[("b", "a")],
+        );
+        let d = diff(&old, &new);
+
+        let gate = Gate {
+            slower: Some(Threshold::Percent(10.0)),
+            critical_path: false,
+        };
+        // a grew 4% and tiny is too fast to gate on percentages.
+        assert_eq!(
+            vec![
+                "total time went from 2000ms to 2540ms (+540ms)",
+                "crate `b` went from 1000ms to 1500ms (+500ms)",
+            ],
+            gate.regressions(&d)
+        );
+
+        let gate = Gate {
+            slower: Some(Threshold::Millis(30.0)),
+            critical_path: true,
+        };
+        assert_eq!(5, gate.regressions(&d).len());
+
+        let gate = Gate {
+            slower: None,
+            critical_path: true,
+        };
+        assert_eq!(
+            vec!["critical path went from 2000ms to 2540ms (+540ms)"],
+            gate.regressions(&d)
+        );
+        assert!(Gate::default().regressions(&d).is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::model::{BuildGraph, BuildInfo, Milliseconds};
+use crate::output::json;
+
+/// A run recorded in the history directory.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub path: PathBuf,
+    pub build_info: BuildInfo,
+    pub total_ms: Milliseconds,
+    pub critical_path_ms: Milliseconds,
+}
+
+/// How many runs of the same commit in the same second are numbered before
+/// giving up.
+const MAX_RUNS_PER_SECOND: u32 = 1000;
+
+/// Where runs are recorded: `<out-dir>/history/`.
+pub fn history_dir(out_dir: &Path) -> PathBuf {
+    out_dir.join("history")
+}
+
+/// Record an analyzed run as a JSON report named after its timestamp and git
+/// commit, e.g. `1760000000-0123456789ab.json`. Runs of the same commit in the
+/// same second are numbered: `1760000000-0123456789ab-2.json`.
+pub fn record(graph: &BuildGraph, out_dir: &Path) -> anyhow::Result<PathBuf> {
+    let dir = history_dir(out_dir);
+    std::fs::create_dir_all(&dir)?;
+    let report = json::generate_json(graph)?;
+    let stem = file_stem(&graph.build_info);
+    for n in 1..=MAX_RUNS_PER_SECOND {
+        let path = match n {
+            1 => dir.join(format!("{stem}.json")),
+            n => dir.join(format!("{stem}-{n}.json")),
+        };
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                file.write_all(report.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    anyhow::bail!(
+        "{MAX_RUNS_PER_SECOND} runs already recorded as {stem} in {}",
+        dir.display()
+    )
+}
+
+fn file_stem(info: &BuildInfo) -> String {
+    let commit = match &info.git_commit {
+        Some(commit) => commit.chars().take(12).collect(),
+        None => "unversioned".to_string(),
+    };
+    let dirty = if info.git_dirty { "-dirty" } else { "" };
+    format!("{}-{commit}{dirty}", info.timestamp)
+}
+
+/// Which of the runs recorded in the same second a report is: 1 for
+/// `<stem>.json`, `n` for `<stem>-<n>.json`.
+fn run_number(path: &Path) -> u32 {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        // Skip the timestamp and commit, which may itself be all digits.
+        .and_then(|stem| stem.split('-').skip(2).filter(|s| *s != "dirty").last())
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1)
+}
+
+/// All recorded runs, oldest first. Reports that can't be read (e.g. from an
+/// incompatible version) are skipped with a warning.
+pub fn load(out_dir: &Path) -> anyhow::Result<Vec<HistoryEntry>> {
+    let dir = history_dir(out_dir);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for file in std::fs::read_dir(&dir)? {
+        let path = file?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        match json::read_report(&path) {
+            Ok(graph) => entries.push(HistoryEntry {
+                total_ms: graph.total_ms(),
+                critical_path_ms: graph.critical_path_ms,
+                build_info: graph.build_info,
+                path,
+            }),
+            Err(e) => tracing::warn!("skipping {e}"),
+        }
+    }
+    entries.sort_by(|a, b| {
+        (a.build_info.timestamp, run_number(&a.path))
+            .cmp(&(b.build_info.timestamp, run_number(&b.path)))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::timed_graph;
+
+    #[test]
+    fn runs_are_recorded_and_loaded_in_order() {
+        let out_dir =
+            std::env::temp_dir().join(format!("goodtimes-history-{}", std::process::id()));
+        let mut slow = timed_graph(&[("a", 0.0, 2.0)], &[]);
+        slow.build_info.timestamp = 200;
+        slow.build_info.git_commit = Some("0123456789abcdef".to_string());
+        slow.build_info.git_dirty = true;
+        let mut fast = timed_graph(&[("a", 0.0, 1.0)], &[]);
+        fast.build_info.timestamp = 100;
+
+        let path = record(&slow, &out_dir).unwrap();
+        assert!(path.ends_with("history/200-0123456789ab-dirty.json"));
+        record(&fast, &out_dir).unwrap();
+        // Another run of the same commit in the same second.
+        let mut slower = timed_graph(&[("a", 0.0, 3.0)], &[]);
+        slower.build_info = slow.build_info.clone();
+        let path = record(&slower, &out_dir).unwrap();
+        assert!(path.ends_with("history/200-0123456789ab-dirty-2.json"));
+        std::fs::write(history_dir(&out_dir).join("notes.txt"), "ignored").unwrap();
+        // The tenth run of the same second sorts after the second.
+        let mut slowest = timed_graph(&[("a", 0.0, 4.0)], &[]);
+        slowest.build_info = slow.build_info.clone();
+        std::fs::write(
+            history_dir(&out_dir).join("200-0123456789ab-dirty-10.json"),
+            json::generate_json(&slowest).unwrap(),
+        )
+        .unwrap();
+
+        let entries = load(&out_dir).unwrap();
+        std::fs::remove_dir_all(&out_dir).unwrap();
+        assert_eq!(
+            vec![(100, 1000.0), (200, 2000.0), (200, 3000.0), (200, 4000.0)],
+            entries
+                .iter()
+                .map(|e| (e.build_info.timestamp, f64::from(e.total_ms)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn run_numbers() {
+        assert_eq!(1, run_number(Path::new("h/200-0123456789ab.json")));
+        assert_eq!(1, run_number(Path::new("h/200-012345678901-dirty.json")));
+        assert_eq!(12, run_number(Path::new("h/200-012345678901-12.json")));
+        assert_eq!(3, run_number(Path::new("h/200-unversioned-dirty-3.json")));
+    }
+}
@@ -1,46 +1,298 @@
+use std::io::{IsTerminal, Write};
 use std::path::Path;
 
+use anyhow::Context;
 use clap::Parser;
 
 mod cargo_ops;
 mod cli;
+mod diff;
+mod history;
 mod model;
 mod output;
+mod whatif;
 
 fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
+    // Log to stderr so reports can be written to stdout.
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
 
     let cli::Cargo::Goodtimes(args) = cli::Cargo::parse();
+    match &args.command {
+        Some(cli::Command::Diff(diff_args)) => return run_diff(diff_args),
+        Some(cli::Command::History(history_args)) => return run_history(history_args),
+        Some(cli::Command::WhatIf(what_if_args)) => return run_what_if(what_if_args),
+        None => {}
+    }
+
+    anyhow::ensure!(
+        args.baseline.is_none() || args.format.supports_diff(),
+        output::UNSUPPORTED_DIFF_FORMAT
+    );
+    anyhow::ensure!(
+        args.baseline.is_some() || !args.gate.is_set(),
+        "--fail-if-slower and --fail-if-critical-path-grows need a --baseline to compare against"
+    );
 
     let manifest_path = resolve_manifest(&args.manifest_path)?;
     tracing::info!("using manifest: {manifest_path}");
 
-    let mut graph = cargo_ops::metadata::load_dependency_graph(&manifest_path, args.include_deps)?;
+    let opts = build_options(&args, manifest_path);
+
+    let mut graph = cargo_ops::metadata::load_dependency_graph(&opts, args.include_deps)?;
     tracing::info!("loaded {} crates", graph.nodes.len());
 
-    if args.include_deps {
+    let runs = match &args.from_timings {
+        Some(path) => {
+            let timing_html = cargo_ops::build::resolve_timing_html(path)?;
+            tracing::info!("reading timings from {}", timing_html.display());
+            let units = cargo_ops::timings::load_timing_html(&timing_html)?;
+            let summary = cargo_ops::timings::load_timing_summary(&timing_html)?;
+            graph.build_info = cargo_ops::info::imported(&timing_html, summary, &units);
+            vec![units]
+        }
+        None => {
+            graph.build_info = cargo_ops::info::collect(&opts);
+            cargo_ops::info::add_toolchain(&mut graph.build_info, &opts.manifest_path);
+            graph.build_info.runs = args.runs;
+            (1..=args.runs)
+                .map(|run| {
+                    if args.runs > 1 {
+                        tracing::info!("timed build {run} of {}", args.runs);
+                    }
+                    build_with_timings(&opts, &graph, args.include_deps)
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?
+        }
+    };
+    match runs.as_slice() {
+        [units] => cargo_ops::build::apply_timings(&mut graph, units),
+        runs => cargo_ops::build::apply_runs(&mut graph, runs),
+    }
+    if let Some(validation) = cargo_ops::simulate::validate(&graph)
+        && let Some(error) = validation.error_percent()
+    {
+        tracing::info!(
+            "simulated build with {} jobs: {} predicted, {} measured ({error:+.1}%), \
+             crate starts off by {} on average",
+            validation.jobs,
+            output::format_ms(validation.predicted_ms.into()),
+            output::format_ms(validation.measured_ms.into()),
+            output::format_ms(validation.start_error_ms.into()),
+        );
+    }
+
+    let out_dir = cargo_ops::build::find_target_dir(&opts.manifest_path)?.join("cargo-goodtimes");
+    // An imported report isn't a run of this tree.
+    if graph.build_info.timings_source.is_none() {
+        match history::record(&graph, &out_dir) {
+            Ok(path) => tracing::info!("recorded run in {}", path.display()),
+            Err(e) => tracing::warn!("could not record run in history: {e}"),
+        }
+    }
+    let summary = output::SummaryOptions {
+        top: args.top,
+        near_critical: args.near_critical,
+    };
+    match &args.baseline {
+        Some(baseline) => {
+            let old = output::json::read_report(baseline)?;
+            let build_diff = diff::diff(&old, &graph);
+            output::write_report(
+                output::Report::Diff(&build_diff),
+                args.format,
+                args.output.as_deref(),
+                &out_dir,
+                !args.no_open,
+                summary,
+            )?;
+            check_regressions(&args.gate.gate(), &build_diff)
+        }
+        None => output::write_report(
+            output::Report::Build(&graph),
+            args.format,
+            args.output.as_deref(),
+            &out_dir,
+            !args.no_open,
+            summary,
+        ),
+    }
+}
+
+/// Compare two JSON reports.
+fn run_diff(args: &cli::DiffArgs) -> anyhow::Result<()> {
+    let old = output::json::read_report(&args.old)?;
+    let new = output::json::read_report(&args.new)?;
+    let build_diff = diff::diff(&old, &new);
+    let out_dir = args.new.parent().unwrap_or_else(|| Path::new("."));
+    output::write_report(
+        output::Report::Diff(&build_diff),
+        args.format,
+        args.output.as_deref(),
+        out_dir,
+        !args.no_open,
+        output::SummaryOptions {
+            top: args.top,
+            ..Default::default()
+        },
+    )?;
+    check_regressions(&args.gate.gate(), &build_diff)
+}
+
+/// List the runs recorded under the workspace's target directory.
+fn run_history(args: &cli::HistoryArgs) -> anyhow::Result<()> {
+    let manifest_path = resolve_manifest(&args.manifest_path)?;
+    let out_dir = cargo_ops::build::find_target_dir(&manifest_path)?.join("cargo-goodtimes");
+    let mut entries = history::load(&out_dir)?;
+    anyhow::ensure!(
+        !entries.is_empty(),
+        "no runs recorded in {}",
+        history::history_dir(&out_dir).display()
+    );
+    if let Some(last) = args.last {
+        entries.drain(..entries.len().saturating_sub(last));
+    }
+    let stdout = std::io::stdout();
+    let style = output::text::TextStyle::detect(stdout.is_terminal());
+    stdout
+        .lock()
+        .write_all(output::history::generate_text(&entries, style).as_bytes())?;
+    Ok(())
+}
+
+/// Predict how a build would change with dependencies removed or added.
+fn run_what_if(args: &cli::WhatIfArgs) -> anyhow::Result<()> {
+    let report = match &args.report {
+        Some(report) => report.clone(),
+        None => {
+            let manifest_path = resolve_manifest(&args.manifest_path)?;
+            let out_dir =
+                cargo_ops::build::find_target_dir(&manifest_path)?.join("cargo-goodtimes");
+            history::load(&out_dir)?
+                .pop()
+                .map(|entry| entry.path)
+                .with_context(|| {
+                    format!(
+                        "no runs recorded in {}; pass --report",
+                        history::history_dir(&out_dir).display()
+                    )
+                })?
+        }
+    };
+    tracing::info!("changing {}", report.display());
+    let graph = output::json::read_report(&report)?;
+    let stdout = std::io::stdout();
+    let style = output::text::TextStyle::detect(stdout.is_terminal());
+
+    let mut scenario = match &args.hash {
+        Some(hash) => whatif::Scenario::from_hash(hash)?,
+        None => whatif::Scenario::default(),
+    };
+    scenario.remove.extend(args.remove.iter().cloned());
+    scenario.add.extend(args.add.iter().cloned());
+    scenario.durations.extend(args.duration.iter().cloned());
+    anyhow::ensure!(
+        !scenario.is_empty() || args.rank.is_some(),
+        "nothing to change: pass --remove, --add, --duration, --hash or --rank"
+    );
+    let changed = whatif::apply(&graph, &scenario)?;
+
+    let jobs = args
+        .jobs
+        .or_else(|| cargo_ops::simulate::measured_jobs(&graph.build_info))
+        .context("the report doesn't record how many jobs the build ran with; pass -j")?;
+    let rendered = match args.rank {
+        // Rank on top of any other changes.
+        Some(duration) => output::whatif::generate_ranking_text(
+            &changed,
+            &whatif::rank(&changed, duration, jobs),
+            duration,
+            jobs,
+            args.top,
+            style,
+        ),
+        None => {
+            let build_diff = diff::diff(
+                &whatif::predict(&graph, jobs),
+                &whatif::predict(&changed, jobs),
+            );
+            output::whatif::generate_text(&build_diff, jobs, style)
+        }
+    };
+    stdout.lock().write_all(rendered.as_bytes())?;
+    Ok(())
+}
+
+/// Fail if the comparison shows regressions beyond the gate.
+fn check_regressions(gate: &diff::Gate, build_diff: &diff::BuildDiff) -> anyhow::Result<()> {
+    let regressions = gate.regressions(build_diff);
+    for regression in &regressions {
+        tracing::error!("{regression}");
+    }
+    anyhow::ensure!(
+        regressions.is_empty(),
+        "build time regressed ({} check(s) failed)",
+        regressions.len()
+    );
+    Ok(())
+}
+
+fn build_options(args: &cli::Args, manifest_path: String) -> cargo_ops::build::BuildOptions {
+    cargo_ops::build::BuildOptions {
+        manifest_path,
+        mode: args.mode,
+        profile: args.profile.clone(),
+        features: args.features.iter().flatten().cloned().collect(),
+        all_features: args.all_features,
+        no_default_features: args.no_default_features,
+        targets: args.targets.clone(),
+        selection: cargo_ops::build::PackageSelection {
+            packages: args.packages.clone(),
+            workspace: args.workspace,
+            exclude: args.exclude.clone(),
+            bins: args.bins.clone(),
+            lib: args.lib,
+        },
+        cargo_args: args.cargo_args.clone(),
+    }
+}
+
+/// Clean the crates under analysis and run a timed build.
+fn build_with_timings(
+    opts: &cargo_ops::build::BuildOptions,
+    graph: &model::BuildGraph,
+    include_deps: bool,
+) -> anyhow::Result<Vec<cargo_ops::timings::UnitTiming>> {
+    let manifest_path = opts.manifest_path.as_str();
+    let target_args = opts.targets.iter().flat_map(|t| ["--target", t]);
+
+    if include_deps {
         // Full clean so third-party deps are also recompiled and timed.
         tracing::info!("cleaning all crates…");
         let status = std::process::Command::new("cargo")
-            .args(["clean", "--manifest-path", &manifest_path])
+            .args(["clean", "--manifest-path", manifest_path])
+            .args(target_args)
             .status()?;
         anyhow::ensure!(status.success(), "cargo clean failed");
     } else {
         // Ensure third-party deps are compiled before we clean workspace crates.
         tracing::info!("Pre-building dependencies...");
-        cargo_ops::build::prebuild_deps(
-            &manifest_path,
-            &args.profile,
-            &args.features,
-            args.all_features,
-        )?;
-
-        // Clean only workspace crates so external deps stay cached.
-        let ws_packages = cargo_ops::metadata::workspace_package_names(&manifest_path)?;
+        cargo_ops::build::prebuild_deps(opts)?;
+
+        // Clean only the selected workspace crates (and the workspace crates
+        // they depend on) so external deps stay cached.
+        let ws_packages: Vec<&str> = graph
+            .nodes
+            .values()
+            .filter(|n| n.is_workspace_member)
+            .map(|n| n.name.as_str())
+            .collect();
         tracing::info!("cleaning {} workspace crate(s)…", ws_packages.len());
         let mut clean_cmd = std::process::Command::new("cargo");
-        clean_cmd.args(["clean", "--manifest-path", &manifest_path]);
-        for pkg in &ws_packages {
+        clean_cmd.args(["clean", "--manifest-path", manifest_path]);
+        clean_cmd.args(target_args);
+        for pkg in ws_packages {
             clean_cmd.args(["-p", pkg]);
         }
         let status = clean_cmd.status()?;
@@ -49,17 +301,9 @@ fn main() -> anyhow::Result<()> {
 
     // Run an initial build to collect timing data.
     tracing::info!("running initial build…");
-    cargo_ops::build::run_build(
-        &manifest_path,
-        &args.profile,
-        &args.features,
-        args.all_features,
-    )?;
-    cargo_ops::build::apply_timings(&mut graph, &manifest_path)?;
+    let units = cargo_ops::build::run_build(opts)?;
     tracing::info!("initial build complete");
-
-    let target_dir = cargo_ops::build::find_target_dir(&manifest_path)?;
-    output::write_and_open(&graph, &target_dir, !args.no_open)
+    Ok(units)
 }
 
 fn resolve_manifest(path: &str) -> anyhow::Result<String> {
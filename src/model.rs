@@ -2,12 +2,17 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub use crate::model::crate_id::CrateId;
-pub use crate::model::milliseconds::Milliseconds;
+pub use crate::model::milliseconds::{Milliseconds, parse_duration_ms};
 mod crate_id {
     use serde::{Deserialize, Serialize};
-    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
     pub struct CrateId(String);
     use cargo_metadata::Package;
+    impl CrateId {
+        pub fn as_str(&self) -> &str {
+            &self.0
+        }
+    }
     impl From<&Package> for CrateId {
         fn from(pkg: &Package) -> Self {
             CrateId(format!("{}@{}", pkg.name, pkg.version))
@@ -71,12 +76,28 @@ mod milliseconds {
             Milliseconds(self.0 + other.0)
         }
     }
+    impl From<Milliseconds> for f64 {
+        fn from(value: Milliseconds) -> Self {
+            value.0
+        }
+    }
     impl Milliseconds {
         pub fn zero() -> Self {
             Milliseconds(0.0)
         }
     }
 
+    /// Parse a non-negative duration such as `500ms` or `2.5s` into
+    /// milliseconds.
+    pub fn parse_duration_ms(value: &str) -> Option<f64> {
+        let (number, scale) = match value.strip_suffix("ms") {
+            Some(ms) => (ms, 1.0),
+            None => (value.strip_suffix('s')?, 1000.0),
+        };
+        let number: f64 = number.trim().parse().ok()?;
+        (number >= 0.0).then_some(number * scale)
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -89,6 +110,14 @@ mod milliseconds {
             assert_eq!(25.0, sum.0);
         }
 
+        #[test]
+        fn durations_parse_to_milliseconds() {
+            assert_eq!(Some(500.0), parse_duration_ms("500ms"));
+            assert_eq!(Some(2500.0), parse_duration_ms("2.5s"));
+            assert_eq!(None, parse_duration_ms("500"));
+            assert_eq!(None, parse_duration_ms("-1s"));
+        }
+
         #[test]
         fn milliseconds_zero() {
             let zero = Milliseconds::zero();
@@ -110,6 +139,130 @@ pub struct CrateNode {
     /// Whether the artifact was fresh (cached) during the last build.
     pub fresh: bool,
     pub features: Vec<String>,
+    /// Whether the library target is a proc-macro.
+    pub is_proc_macro: bool,
+    /// Offset from `start_ms` at which the library's metadata (`.rmeta`) was
+    /// ready and pipelined dependents could start.
+    pub rmeta_ms: Option<Milliseconds>,
+    /// Individual compilation units (build script, lib, bins, …), sorted by start.
+    pub units: Vec<CrateUnit>,
+    /// Spread of `start_ms` over repeated runs (`--runs`).
+    #[serde(default)]
+    pub start_stats: Option<Stats>,
+    /// Spread of `duration_ms` over repeated runs (`--runs`).
+    #[serde(default)]
+    pub duration_stats: Option<Stats>,
+    /// Earliest the crate could start with unlimited parallelism (CPM).
+    #[serde(default)]
+    pub earliest_start_ms: Option<Milliseconds>,
+    /// Latest the crate could start without delaying the build.
+    #[serde(default)]
+    pub latest_start_ms: Option<Milliseconds>,
+    /// How much the crate could be delayed or grow without delaying the
+    /// build; zero on the critical path.
+    #[serde(default)]
+    pub slack_ms: Option<Milliseconds>,
+}
+
+/// Summary of a measurement repeated over several runs.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub median: Milliseconds,
+    pub min: Milliseconds,
+    pub max: Milliseconds,
+    /// Population standard deviation.
+    pub stddev: Milliseconds,
+}
+
+impl Stats {
+    /// Summarize the samples; `None` if there are none.
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len();
+        let median = if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        };
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let variance = sorted.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n as f64;
+        Some(Stats {
+            median: median.into(),
+            min: sorted[0].into(),
+            max: sorted[n - 1].into(),
+            stddev: variance.sqrt().into(),
+        })
+    }
+}
+
+/// What a single compilation unit builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnitKind {
+    /// Compiling the build script.
+    BuildScript,
+    /// Running the build script.
+    BuildScriptRun,
+    Lib,
+    ProcMacro,
+    Bin,
+    Test,
+    Bench,
+    Example,
+    Doc,
+}
+
+impl UnitKind {
+    /// Order in which a crate's units are built: a build script is compiled,
+    /// then run, then the library is built, and finally everything linking it.
+    pub fn stage(self) -> u8 {
+        match self {
+            UnitKind::BuildScript => 0,
+            UnitKind::BuildScriptRun => 1,
+            UnitKind::Lib | UnitKind::ProcMacro => 2,
+            UnitKind::Bin
+            | UnitKind::Test
+            | UnitKind::Bench
+            | UnitKind::Example
+            | UnitKind::Doc => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrateUnit {
+    pub kind: UnitKind,
+    /// Target name (library or binary name, `build-script-build` for build scripts).
+    pub target: String,
+    /// When this unit started compiling (ms from build start).
+    pub start_ms: Milliseconds,
+    pub duration_ms: Milliseconds,
+    /// Offset from `start_ms` at which the unit's metadata was ready, if known.
+    pub rmeta_ms: Option<Milliseconds>,
+    /// Target triple the unit was built for when building with `--target`;
+    /// `None` for host units (build scripts, proc-macros and their dependencies).
+    pub platform: Option<String>,
+}
+
+/// Identifies one unit of a crate, e.g. a step on the critical path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UnitRef {
+    pub id: CrateId,
+    pub kind: UnitKind,
+    pub target: String,
+    pub platform: Option<String>,
+}
+
+/// A chain of crates through the build, each waiting on the one before, and
+/// how long it takes with unlimited parallelism.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyChain {
+    pub crates: Vec<CrateId>,
+    pub length_ms: Milliseconds,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -119,11 +272,311 @@ pub struct DepEdge {
     pub dep_kinds: Vec<String>,
 }
 
+impl DepEdge {
+    /// Whether the dependency is declared with the given cargo kind
+    /// (`"Normal"`, `"Build"` or `"Development"`).
+    pub fn has_kind(&self, kind: &str) -> bool {
+        self.dep_kinds.iter().any(|k| k == kind)
+    }
+
+    /// Whether the dependency gates the crate's library. Edges without
+    /// recorded kinds are treated as normal dependencies.
+    pub fn is_normal(&self) -> bool {
+        self.dep_kinds.is_empty()
+            || self
+                .dep_kinds
+                .iter()
+                .any(|k| k != "Build" && k != "Development")
+    }
+
+    /// Whether the edge is only a build-dependency, which gates the crate's
+    /// build script but not its own code.
+    pub fn is_build_only(&self) -> bool {
+        !self.dep_kinds.is_empty() && self.dep_kinds.iter().all(|k| k == "Build")
+    }
+
+    /// Whether the edge is only a dev-dependency, which gates tests, benches
+    /// and examples but not the crate's library.
+    pub fn is_dev_only(&self) -> bool {
+        !self.dep_kinds.is_empty() && self.dep_kinds.iter().all(|k| k == "Development")
+    }
+}
+
+/// The cargo command whose compilation was timed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildMode {
+    #[default]
+    Check,
+    Build,
+    Test,
+    Bench,
+    Clippy,
+    Doc,
+}
+
+impl BuildMode {
+    /// Cargo subcommand (and flags) that compiles without running anything.
+    pub fn cargo_args(self) -> &'static [&'static str] {
+        match self {
+            BuildMode::Check => &["check"],
+            BuildMode::Build => &["build"],
+            BuildMode::Test => &["test", "--no-run"],
+            BuildMode::Bench => &["bench", "--no-run"],
+            BuildMode::Clippy => &["clippy"],
+            BuildMode::Doc => &["doc"],
+        }
+    }
+
+    /// Whether the command compiles tests or benches (and examples), which
+    /// link dev-dependencies.
+    pub fn compiles_dev_targets(self) -> bool {
+        matches!(self, BuildMode::Test | BuildMode::Bench)
+    }
+}
+
+impl std::fmt::Display for BuildMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.cargo_args()[0])
+    }
+}
+
+/// Toolchain and configuration of the measured build, so a shared report
+/// says exactly how it was produced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// What was measured.
+    pub mode: BuildMode,
+    pub profile: String,
+    pub features: Vec<String>,
+    pub all_features: bool,
+    pub no_default_features: bool,
+    /// Target triples that were built for; empty for the host.
+    pub targets: Vec<String>,
+    /// Extra arguments that were passed through to cargo.
+    pub cargo_args: Vec<String>,
+    /// Parallel jobs requested with `-j` or `CARGO_BUILD_JOBS`; none for cargo's default.
+    pub jobs: Option<i32>,
+    /// CPUs available to the build; zero if unknown.
+    pub available_cpus: usize,
+    pub rustflags: Option<String>,
+    /// Output of `rustc -vV`.
+    pub rustc_version: Option<String>,
+    pub cargo_version: Option<String>,
+    /// Host target triple.
+    pub host: Option<String>,
+    pub goodtimes_version: String,
+    /// When the analysis ran, or for an imported timing report when the build
+    /// started, in seconds since the Unix epoch; zero if unknown.
+    pub timestamp: u64,
+    /// Commit checked out in the workspace, if it is a git repository.
+    pub git_commit: Option<String>,
+    /// Whether the workspace had uncommitted changes.
+    pub git_dirty: bool,
+    /// The timing report that was analyzed, when not timed locally.
+    pub timings_source: Option<String>,
+    /// How many timed builds the report aggregates (`--runs`).
+    #[serde(default)]
+    pub runs: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildGraph {
     pub nodes: HashMap<CrateId, CrateNode>,
     pub edges: Vec<DepEdge>,
     pub roots: Vec<CrateId>,
+    /// How the measured build was produced.
+    pub build_info: BuildInfo,
     /// Node IDs on the critical path (longest accumulated compile time).
     pub critical_path: Vec<CrateId>,
+    /// Length of the critical path.
+    pub critical_path_ms: Milliseconds,
+    /// The units making up the critical path, in build order.
+    pub critical_units: Vec<UnitRef>,
+    /// The longest distinct dependency chains, longest first: the critical
+    /// path, then the chains that take over as it is shortened.
+    #[serde(default)]
+    pub longest_chains: Vec<DependencyChain>,
+}
+
+impl BuildGraph {
+    /// Crates off the critical path whose slack is within `percent` of the
+    /// critical path's length, least slack first: the crates that become
+    /// critical first when they grow or the critical path shrinks.
+    pub fn near_critical(&self, percent: f64) -> Vec<&CrateNode> {
+        let limit = f64::from(self.critical_path_ms) * percent / 100.0;
+        let mut nodes: Vec<(&CrateNode, f64)> = self
+            .nodes
+            .values()
+            .filter(|n| n.duration_ms.is_some())
+            .filter_map(|n| Some((n, f64::from(n.slack_ms?))))
+            .filter(|&(_, slack)| slack >= 1.0 && slack <= limit)
+            .collect();
+        nodes.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.id.cmp(&b.0.id)));
+        nodes.into_iter().map(|(n, _)| n).collect()
+    }
+
+    /// Wall time of the build: when the last crate finished.
+    pub fn total_ms(&self) -> Milliseconds {
+        self.nodes
+            .values()
+            .filter_map(|n| Some(n.start_ms? + n.duration_ms?))
+            .fold(
+                Milliseconds::zero(),
+                |max, end| if end > max { end } else { max },
+            )
+    }
+}
+
+/// Small graphs for tests.
+#[cfg(test)]
+pub mod fixtures {
+    use super::*;
+    use crate::cargo_ops::timings::UnitTiming;
+
+    pub fn id(name: &str) -> CrateId {
+        CrateId::from(&cargo_metadata::PackageId {
+            repr: format!("{name}@0.1.0"),
+        })
+    }
+
+    pub fn unit(name: &str, target: &str, start: f64, duration: f64) -> UnitTiming {
+        UnitTiming {
+            name: name.to_string(),
+            version: "0.1.0".to_string(),
+            target: target.to_string(),
+            start,
+            duration,
+            rmeta_time: None,
+            platform: None,
+        }
+    }
+
+    /// An untimed graph of workspace crates; edges are `(from, to, kind)`.
+    pub fn graph(names: &[&str], edges: &[(&str, &str, &str)]) -> BuildGraph {
+        let nodes = names
+            .iter()
+            .map(|name| {
+                let node = CrateNode {
+                    id: id(name),
+                    name: name.to_string(),
+                    version: "0.1.0".to_string(),
+                    is_workspace_member: true,
+                    duration_ms: None,
+                    start_ms: None,
+                    fresh: false,
+                    features: Vec::new(),
+                    is_proc_macro: false,
+                    rmeta_ms: None,
+                    units: Vec::new(),
+                    start_stats: None,
+                    duration_stats: None,
+                    earliest_start_ms: None,
+                    latest_start_ms: None,
+                    slack_ms: None,
+                };
+                (node.id.clone(), node)
+            })
+            .collect();
+        let edges = edges
+            .iter()
+            .map(|(from, to, kind)| DepEdge {
+                from: id(from),
+                to: id(to),
+                dep_kinds: vec![kind.to_string()],
+            })
+            .collect();
+        BuildGraph {
+            nodes,
+            edges,
+            roots: Vec::new(),
+            build_info: BuildInfo::default(),
+            critical_path: Vec::new(),
+            critical_path_ms: Milliseconds::zero(),
+            critical_units: Vec::new(),
+            longest_chains: Vec::new(),
+        }
+    }
+
+    /// A graph of library crates timed as `(name, start_s, duration_s)`,
+    /// with normal dependency edges `(from, to)`.
+    pub fn timed_graph(crates: &[(&str, f64, f64)], edges: &[(&str, &str)]) -> BuildGraph {
+        let names: Vec<&str> = crates.iter().map(|c| c.0).collect();
+        let edges: Vec<(&str, &str, &str)> = edges
+            .iter()
+            .map(|&(from, to)| (from, to, "Normal"))
+            .collect();
+        let mut g = graph(&names, &edges);
+        let units: Vec<UnitTiming> = crates
+            .iter()
+            .map(|&(name, start, duration)| unit(name, "", start, duration))
+            .collect();
+        crate::cargo_ops::build::apply_timings(&mut g, &units);
+        g
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_from_samples() {
+        let stats = Stats::from_samples(&[4.0, 1.0, 3.0, 2.0]).unwrap();
+        assert_eq!(Milliseconds::from(2.5), stats.median);
+        assert_eq!(Milliseconds::from(1.0), stats.min);
+        assert_eq!(Milliseconds::from(4.0), stats.max);
+        assert_eq!(Milliseconds::from(1.25f64.sqrt()), stats.stddev);
+        assert_eq!(
+            Milliseconds::from(2.0),
+            Stats::from_samples(&[3.0, 2.0, 1.0]).unwrap().median
+        );
+        assert!(Stats::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn build_mode_cargo_args() {
+        assert_eq!(["check"], BuildMode::Check.cargo_args());
+        assert_eq!(["build"], BuildMode::Build.cargo_args());
+        assert_eq!(["test", "--no-run"], BuildMode::Test.cargo_args());
+        assert_eq!(["bench", "--no-run"], BuildMode::Bench.cargo_args());
+        assert_eq!(["clippy"], BuildMode::Clippy.cargo_args());
+        assert_eq!(["doc"], BuildMode::Doc.cargo_args());
+    }
+
+    #[test]
+    fn only_test_and_bench_keep_dev_dependencies() {
+        let modes = [
+            BuildMode::Check,
+            BuildMode::Build,
+            BuildMode::Test,
+            BuildMode::Bench,
+            BuildMode::Clippy,
+            BuildMode::Doc,
+        ];
+        let with_dev: Vec<_> = modes
+            .into_iter()
+            .filter(|m| m.compiles_dev_targets())
+            .collect();
+        assert_eq!(vec![BuildMode::Test, BuildMode::Bench], with_dev);
+    }
+
+    #[test]
+    fn dependency_kinds() {
+        let edge = |kinds: &[&str]| DepEdge {
+            from: fixtures::id("a"),
+            to: fixtures::id("b"),
+            dep_kinds: kinds.iter().map(|k| k.to_string()).collect(),
+        };
+        assert!(edge(&[]).is_normal());
+        assert!(!edge(&[]).is_build_only() && !edge(&[]).is_dev_only());
+        assert!(edge(&["Build"]).is_build_only());
+        assert!(!edge(&["Build"]).is_normal());
+        assert!(edge(&["Development"]).is_dev_only());
+        assert!(!edge(&["Development"]).is_normal());
+        let mixed = edge(&["Build", "Development"]);
+        assert!(!mixed.is_build_only() && !mixed.is_dev_only() && !mixed.is_normal());
+        assert!(mixed.has_kind("Build") && mixed.has_kind("Development"));
+        assert!(edge(&["Normal", "Development"]).is_normal());
+    }
 }
@@ -1,22 +1,161 @@
+use std::io::{IsTerminal, Write};
 use std::path::Path;
 
 use rust_embed::Embed;
 
+use crate::diff::BuildDiff;
 use crate::model::BuildGraph;
 
+pub mod diff;
+pub mod dot;
+pub mod history;
+pub mod json;
+pub mod markdown;
+pub mod svg;
+pub mod text;
+pub mod trace;
+pub mod whatif;
+
 #[derive(Embed)]
 #[folder = "frontend/dist/assets"]
 struct FrontendAsset;
 
-pub fn write_and_open(graph: &BuildGraph, target_dir: &Path, open: bool) -> anyhow::Result<()> {
-    let html = generate_html(graph)?;
-    let out_dir = target_dir.join("cargo-goodtimes");
-    std::fs::create_dir_all(&out_dir)?;
-    let out_path = out_dir.join("index.html");
-    std::fs::write(&out_path, &html)?;
+/// Report format written by `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Interactive, self-contained HTML report.
+    Html,
+    /// Versioned JSON document for scripts and dashboards.
+    Json,
+    /// Chrome trace-event JSON for Perfetto or `chrome://tracing`.
+    Trace,
+    /// Static Gantt chart for docs and PR comments.
+    Svg,
+    /// Terminal summary and Gantt chart of the slowest crates, printed to
+    /// stdout unless `--output` is given.
+    Text,
+    /// Markdown summary for CI job summaries and PR comments.
+    Markdown,
+    /// Graphviz DOT graph weighted by compile time.
+    Dot,
+}
+
+impl OutputFormat {
+    /// Whether a comparison of two builds can be written in this format.
+    pub fn supports_diff(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Html | OutputFormat::Text | OutputFormat::Markdown
+        )
+    }
+
+    fn default_file_name(self, report: Report) -> anyhow::Result<&'static str> {
+        Ok(match (report, self) {
+            (Report::Build(_), OutputFormat::Html) => "index.html",
+            (Report::Build(_), OutputFormat::Json) => "report.json",
+            (Report::Build(_), OutputFormat::Trace) => "trace.json",
+            (Report::Build(_), OutputFormat::Svg) => "gantt.svg",
+            (Report::Build(_), OutputFormat::Text) => "summary.txt",
+            (Report::Build(_), OutputFormat::Markdown) => "summary.md",
+            (Report::Build(_), OutputFormat::Dot) => "graph.dot",
+            (Report::Diff(_), OutputFormat::Html) => "diff.html",
+            (Report::Diff(_), OutputFormat::Text) => "diff.txt",
+            (Report::Diff(_), OutputFormat::Markdown) => "diff.md",
+            (Report::Diff(_), _) => anyhow::bail!(UNSUPPORTED_DIFF_FORMAT),
+        })
+    }
+
+    fn render(
+        self,
+        report: Report,
+        summary: SummaryOptions,
+        to_terminal: bool,
+    ) -> anyhow::Result<String> {
+        let style = text::TextStyle::detect(to_terminal);
+        match report {
+            Report::Build(graph) => match self {
+                OutputFormat::Html => generate_html(graph),
+                OutputFormat::Json => json::generate_json(graph),
+                OutputFormat::Trace => trace::generate_trace(graph),
+                OutputFormat::Svg => svg::generate_svg(graph),
+                OutputFormat::Text => Ok(text::generate_text(graph, summary, style)),
+                OutputFormat::Markdown => Ok(markdown::generate_markdown(graph, summary)),
+                OutputFormat::Dot => Ok(dot::generate_dot(graph)),
+            },
+            Report::Diff(build_diff) => match self {
+                OutputFormat::Html => Ok(diff::generate_html(build_diff, summary.top)),
+                OutputFormat::Text => Ok(diff::generate_text(build_diff, summary.top, style)),
+                OutputFormat::Markdown => Ok(diff::generate_markdown(build_diff, summary.top)),
+                _ => anyhow::bail!(UNSUPPORTED_DIFF_FORMAT),
+            },
+        }
+    }
+}
+
+pub const UNSUPPORTED_DIFF_FORMAT: &str =
+    "comparisons can only be written as html, text or markdown";
+
+/// What text and Markdown summaries list.
+#[derive(Debug, Clone, Copy)]
+pub struct SummaryOptions {
+    /// Number of slowest (or most changed) crates.
+    pub top: usize,
+    /// Crates off the critical path are listed as near-critical if their
+    /// slack is within this percentage of the critical path.
+    pub near_critical: f64,
+}
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        Self {
+            top: 20,
+            near_critical: 10.0,
+        }
+    }
+}
+
+/// What to write: an analyzed build, or a comparison of two builds.
+#[derive(Debug, Clone, Copy)]
+pub enum Report<'a> {
+    Build(&'a BuildGraph),
+    Diff(&'a BuildDiff),
+}
+
+/// Write the report to `output` (`-` for stdout), or into `out_dir` by
+/// default (text goes to stdout). HTML
+/// reports written to a file are opened in the browser if `open` is set.
+pub fn write_report(
+    report: Report,
+    format: OutputFormat,
+    output: Option<&Path>,
+    out_dir: &Path,
+    open: bool,
+    summary: SummaryOptions,
+) -> anyhow::Result<()> {
+    let to_stdout = match output {
+        Some(path) => path == Path::new("-"),
+        None => format == OutputFormat::Text,
+    };
+    if to_stdout {
+        let stdout = std::io::stdout();
+        let rendered = format.render(report, summary, stdout.is_terminal())?;
+        stdout.lock().write_all(rendered.as_bytes())?;
+        return Ok(());
+    }
+
+    let rendered = format.render(report, summary, false)?;
+
+    let out_path = match output {
+        Some(path) => path.to_path_buf(),
+        None => {
+            std::fs::create_dir_all(out_dir)?;
+            out_dir.join(format.default_file_name(report)?)
+        }
+    };
+    std::fs::write(&out_path, &rendered)?;
     tracing::info!("wrote {}", out_path.display());
 
-    if open {
+    if open && format == OutputFormat::Html {
         let url = format!("file://{}", out_path.canonicalize()?.display());
         webbrowser::open(&url)?;
     }
@@ -24,6 +163,29 @@ pub fn write_and_open(graph: &BuildGraph, target_dir: &Path, open: bool) -> anyh
     Ok(())
 }
 
+/// Escape text for HTML and SVG, in element content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Format a duration for summaries: `850ms`, `1.23s`, `12.3s`, `2m 05s`.
+pub(crate) fn format_ms(ms: f64) -> String {
+    let secs = ms / 1000.0;
+    if ms < 1000.0 {
+        format!("{ms:.0}ms")
+    } else if secs < 10.0 {
+        format!("{secs:.2}s")
+    } else if secs < 60.0 {
+        format!("{secs:.1}s")
+    } else {
+        let secs = secs.round() as u64;
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
 fn generate_html(graph: &BuildGraph) -> anyhow::Result<String> {
     // Find the JS and CSS assets (Vite adds content hashes to filenames).
     let mut js_source = None;
@@ -67,3 +229,16 @@ fn generate_html(graph: &BuildGraph) -> anyhow::Result<String> {
 </html>"#
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_are_human_readable() {
+        assert_eq!("850ms", format_ms(850.0));
+        assert_eq!("1.23s", format_ms(1234.0));
+        assert_eq!("12.3s", format_ms(12_345.0));
+        assert_eq!("2m 05s", format_ms(125_000.0));
+    }
+}
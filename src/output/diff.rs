@@ -0,0 +1,360 @@
+use std::fmt::Write;
+
+use crate::diff::{BuildDiff, PathEntry};
+use crate::output::text::TextStyle;
+use crate::output::{escape, format_ms};
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+
+/// A table shared by the text, Markdown and HTML renderings.
+pub(super) struct Table {
+    headers: Vec<&'static str>,
+    pub(super) rows: Vec<Vec<String>>,
+    /// Columns holding numbers, which are right-aligned.
+    numeric: Vec<bool>,
+}
+
+impl Table {
+    pub(super) fn new(headers: Vec<&'static str>, numeric: Vec<bool>) -> Self {
+        Table {
+            headers,
+            rows: Vec::new(),
+            numeric,
+        }
+    }
+
+    pub(super) fn text(&self, style: TextStyle) -> String {
+        let widths: Vec<usize> = (0..self.headers.len())
+            .map(|c| {
+                self.rows
+                    .iter()
+                    .map(|r| r[c].chars().count())
+                    .chain([self.headers[c].chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let line = |cells: Vec<String>| -> String {
+            let padded: Vec<String> = cells
+                .iter()
+                .enumerate()
+                .map(|(c, cell)| {
+                    let pad = " ".repeat(widths[c].saturating_sub(cell.chars().count()));
+                    let cell = paint_change(style, cell);
+                    if self.numeric[c] {
+                        format!("{pad}{cell}")
+                    } else {
+                        format!("{cell}{pad}")
+                    }
+                })
+                .collect();
+            format!("  {}", padded.join("  ").trim_end())
+        };
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}",
+            line(self.headers.iter().map(|h| h.to_string()).collect())
+        );
+        for row in &self.rows {
+            let _ = writeln!(out, "{}", line(row.clone()));
+        }
+        out
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "| {} |", self.headers.join(" | "));
+        let align: Vec<&str> = self
+            .numeric
+            .iter()
+            .map(|&n| if n { "---:" } else { "---" })
+            .collect();
+        let _ = writeln!(out, "| {} |", align.join(" | "));
+        for row in &self.rows {
+            let _ = writeln!(out, "| {} |", row.join(" | "));
+        }
+        out
+    }
+
+    fn html(&self) -> String {
+        let cell = |tag: &str, c: usize, text: &str| {
+            let class = if self.numeric[c] {
+                " class=\"num\""
+            } else {
+                ""
+            };
+            format!("<{tag}{class}>{}</{tag}>", escape(text))
+        };
+        let mut out = String::from("<table>\n<tr>");
+        for (c, header) in self.headers.iter().enumerate() {
+            out.push_str(&cell("th", c, header));
+        }
+        out.push_str("</tr>\n");
+        for row in &self.rows {
+            out.push_str("<tr>");
+            for (c, text) in row.iter().enumerate() {
+                out.push_str(&cell("td", c, text));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+        out
+    }
+}
+
+/// A signed duration, e.g. `+1.20s` or `-850ms`.
+pub(crate) fn format_delta(ms: f64) -> String {
+    let sign = if ms < 0.0 { "-" } else { "+" };
+    format!("{sign}{}", format_ms(ms.abs()))
+}
+
+fn format_percent(old: f64, new: f64) -> String {
+    if old > 0.0 {
+        format!("{:+.1}%", 100.0 * (new - old) / old)
+    } else {
+        String::new()
+    }
+}
+
+/// Color increases red and decreases green.
+fn paint_change(style: TextStyle, cell: &str) -> String {
+    if !style.color || cell.len() < 2 {
+        return cell.to_string();
+    }
+    match cell.as_bytes()[0] {
+        b'+' if cell.as_bytes()[1].is_ascii_digit() => style.paint(RED, cell),
+        b'-' if cell.as_bytes()[1].is_ascii_digit() => style.paint(GREEN, cell),
+        _ => cell.to_string(),
+    }
+}
+
+pub(super) fn summary(diff: &BuildDiff) -> Table {
+    let mut table = Table::new(
+        vec!["", "Old", "New", "Change", "%"],
+        vec![false, true, true, true, true],
+    );
+    for (label, old, new, delta) in [
+        (
+            "Total",
+            diff.old_total_ms,
+            diff.new_total_ms,
+            diff.total_delta_ms(),
+        ),
+        (
+            "Critical path",
+            diff.old_critical_path_ms,
+            diff.new_critical_path_ms,
+            diff.critical_path_delta_ms(),
+        ),
+    ] {
+        let (old, new) = (f64::from(old), f64::from(new));
+        table.rows.push(vec![
+            label.to_string(),
+            format_ms(old),
+            format_ms(new),
+            format_delta(delta),
+            format_percent(old, new),
+        ]);
+    }
+    table
+}
+
+/// The `top` crates whose compile time changed the most.
+pub(super) fn changes(diff: &BuildDiff, top: usize) -> Table {
+    let mut table = Table::new(
+        vec!["Crate", "Old", "New", "Change", "%"],
+        vec![false, true, true, true, true],
+    );
+    for delta in diff
+        .crates
+        .iter()
+        .filter(|c| c.delta_ms().is_some_and(|d| d.abs() >= 1.0))
+        .take(top)
+    {
+        let version = if delta.old_version == delta.new_version {
+            delta.new_version.clone()
+        } else {
+            format!("{} → {}", delta.old_version, delta.new_version)
+        };
+        let ms = |m: Option<crate::model::Milliseconds>| {
+            m.map_or(String::new(), |m| format_ms(m.into()))
+        };
+        table.rows.push(vec![
+            format!("{} {version}", delta.name),
+            ms(delta.old_ms),
+            ms(delta.new_ms),
+            delta.delta_ms().map_or(String::new(), format_delta),
+            delta
+                .delta_percent()
+                .map_or(String::new(), |p| format!("{p:+.1}%")),
+        ]);
+    }
+    table
+}
+
+/// Old and new critical paths, side by side.
+pub(super) fn critical_paths(diff: &BuildDiff) -> Table {
+    let mut table = Table::new(
+        vec!["Old critical path", "", "New critical path", ""],
+        vec![false, true, false, true],
+    );
+    let entry = |e: Option<&PathEntry>| match e {
+        Some(e) => [
+            format!("{} {}", e.name, e.version),
+            e.duration_ms.map_or(String::new(), |d| format_ms(d.into())),
+        ],
+        None => [String::new(), String::new()],
+    };
+    let rows = diff
+        .old_critical_path
+        .len()
+        .max(diff.new_critical_path.len());
+    for i in 0..rows {
+        let [old_name, old_ms] = entry(diff.old_critical_path.get(i));
+        let [new_name, new_ms] = entry(diff.new_critical_path.get(i));
+        table.rows.push(vec![old_name, old_ms, new_name, new_ms]);
+    }
+    table
+}
+
+/// Added and removed crates and dependency edges, as `(heading, items)`.
+pub(super) fn structure(diff: &BuildDiff) -> Vec<(&'static str, Vec<String>)> {
+    let edges = |edges: &[(String, String)]| {
+        edges
+            .iter()
+            .map(|(from, to)| format!("{from} → {to}"))
+            .collect()
+    };
+    [
+        (
+            "Added crates",
+            diff.added
+                .iter()
+                .map(|id| id.as_str().to_string())
+                .collect(),
+        ),
+        (
+            "Removed crates",
+            diff.removed
+                .iter()
+                .map(|id| id.as_str().to_string())
+                .collect(),
+        ),
+        ("Added dependencies", edges(&diff.added_edges)),
+        ("Removed dependencies", edges(&diff.removed_edges)),
+    ]
+    .into_iter()
+    .filter(|(_, items): &(_, Vec<String>)| !items.is_empty())
+    .collect()
+}
+
+pub fn generate_text(diff: &BuildDiff, top: usize, style: TextStyle) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}",
+        style.paint(super::text::BOLD, "Build time comparison")
+    );
+    out.push_str(&summary(diff).text(style));
+
+    let changes = changes(diff, top);
+    if !changes.rows.is_empty() {
+        let _ = writeln!(out, "\nLargest changes");
+        out.push_str(&changes.text(style));
+    }
+    for (heading, items) in structure(diff) {
+        let _ = writeln!(out, "\n{heading}");
+        for item in items {
+            let _ = writeln!(out, "  {item}");
+        }
+    }
+    out.push('\n');
+    out.push_str(&critical_paths(diff).text(style));
+    out
+}
+
+pub fn generate_markdown(diff: &BuildDiff, top: usize) -> String {
+    let mut out = String::from("## Build time comparison\n\n");
+    out.push_str(&summary(diff).markdown());
+
+    let changes = changes(diff, top);
+    if !changes.rows.is_empty() {
+        let _ = writeln!(out, "\n### Largest changes\n");
+        out.push_str(&changes.markdown());
+    }
+    for (heading, items) in structure(diff) {
+        let _ = writeln!(out, "\n### {heading}\n");
+        for item in items {
+            let _ = writeln!(out, "- `{item}`");
+        }
+    }
+    let _ = writeln!(out, "\n### Critical path\n");
+    out.push_str(&critical_paths(diff).markdown());
+    out
+}
+
+pub fn generate_html(diff: &BuildDiff, top: usize) -> String {
+    let mut body = String::from("<h1>Build time comparison</h1>\n");
+    body.push_str(&summary(diff).html());
+
+    let changes = changes(diff, top);
+    if !changes.rows.is_empty() {
+        body.push_str("<h2>Largest changes</h2>\n");
+        body.push_str(&changes.html());
+    }
+    for (heading, items) in structure(diff) {
+        let _ = writeln!(body, "<h2>{heading}</h2>\n<ul>");
+        for item in items {
+            let _ = writeln!(body, "<li><code>{}</code></li>", escape(&item));
+        }
+        body.push_str("</ul>\n");
+    }
+    body.push_str("<h2>Critical path</h2>\n");
+    body.push_str(&critical_paths(diff).html());
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8" />
+<title>cargo goodtimes diff</title>
+<style>
+body {{ background: #0f1117; color: #e1e4ed; font-family: system-ui, sans-serif; margin: 2rem; }}
+h1 {{ font-size: 1.4rem; }}
+h2 {{ font-size: 1.1rem; margin-top: 2rem; }}
+table {{ border-collapse: collapse; font-family: ui-monospace, "SF Mono", monospace; font-size: 0.85rem; }}
+th, td {{ padding: 0.25rem 0.75rem; border-bottom: 1px solid #2a2d3a; text-align: left; }}
+th {{ color: #8b8fa3; font-weight: normal; }}
+.num {{ text-align: right; }}
+code {{ color: #6c7be9; }}
+</style>
+</head>
+<body>
+{body}</body>
+</html>
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::diff;
+    use crate::model::fixtures::timed_graph;
+
+    #[test]
+    fn markdown_diff_shows_changes_and_paths() {
+        let old = timed_graph(&[("a", 0.0, 1.0), ("b", 1.0, 1.0)], &[("b", "a")]);
+        let new = timed_graph(&[("a", 0.0, 1.0), ("b", 1.0, 2.0)], &[("b", "a")]);
+        let md = generate_markdown(&diff(&old, &new), 10);
+        assert!(md.contains("| Total | 2.00s | 3.00s | +1.00s | +50.0% |"));
+        assert!(md.contains("| b 0.1.0 | 1.00s | 2.00s | +1.00s | +100.0% |"));
+        // a didn't change.
+        assert!(!md.contains("| a 0.1.0 | 1.00s | 1.00s |"));
+        assert!(md.contains(
+            "| a 0.1.0 | 1.00s | a 0.1.0 | 1.00s |\n| b 0.1.0 | 1.00s | b 0.1.0 | 2.00s |"
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::model::{BuildGraph, CrateId, CrateNode};
+use crate::output::format_ms;
+
+const MIN_FONT_SIZE: f64 = 10.0;
+const MAX_FONT_SIZE: f64 = 24.0;
+
+/// Export the dependency graph as Graphviz DOT. Edges point from a crate to
+/// its dependency. Nodes are shaded and sized by compile time, critical-path
+/// edges are bold red, build-dependency edges are dashed, dev-dependency edges
+/// are dotted, and dependencies outside the workspace are grouped into their
+/// own cluster.
+pub fn generate_dot(graph: &BuildGraph) -> String {
+    let slowest = graph
+        .nodes
+        .values()
+        .filter_map(|n| n.duration_ms)
+        .map(f64::from)
+        .fold(0.0, f64::max);
+    let critical_edges: HashSet<(&CrateId, &CrateId)> = graph
+        .critical_path
+        .windows(2)
+        .map(|pair| (&pair[1], &pair[0]))
+        .collect();
+
+    let mut nodes: Vec<&CrateNode> = graph.nodes.values().collect();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    let (members, deps): (Vec<&CrateNode>, Vec<&CrateNode>) =
+        nodes.into_iter().partition(|n| n.is_workspace_member);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph build {{");
+    let _ = writeln!(out, "  rankdir=LR;");
+    let _ = writeln!(
+        out,
+        "  node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];"
+    );
+    for node in &members {
+        write_node(&mut out, "  ", node, slowest);
+    }
+    if !deps.is_empty() {
+        let _ = writeln!(out, "  subgraph cluster_dependencies {{");
+        let _ = writeln!(out, "    label=\"dependencies\";");
+        let _ = writeln!(out, "    style=dashed;");
+        for node in &deps {
+            write_node(&mut out, "    ", node, slowest);
+        }
+        let _ = writeln!(out, "  }}");
+    }
+
+    let mut edges: Vec<_> = graph.edges.iter().collect();
+    edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+    for edge in edges {
+        let mut attrs = Vec::new();
+        if edge.is_build_only() {
+            attrs.push("style=dashed".to_string());
+        }
+        if edge.is_dev_only() {
+            attrs.push("style=dotted".to_string());
+        }
+        if critical_edges.contains(&(&edge.from, &edge.to)) {
+            attrs.push("color=red".to_string());
+            attrs.push("penwidth=3".to_string());
+        }
+        if !edge.dep_kinds.is_empty() {
+            attrs.push(format!("tooltip={}", quote(&edge.dep_kinds.join(", "))));
+        }
+        let _ = write!(
+            out,
+            "  {} -> {}",
+            quote(edge.from.as_str()),
+            quote(edge.to.as_str())
+        );
+        if !attrs.is_empty() {
+            let _ = write!(out, " [{}]", attrs.join(", "));
+        }
+        let _ = writeln!(out, ";");
+    }
+    let _ = writeln!(out, "}}");
+    out
+}
+
+fn write_node(out: &mut String, indent: &str, node: &CrateNode, slowest: f64) {
+    let attrs = match node.duration_ms.map(f64::from) {
+        Some(duration) => {
+            let ratio = if slowest > 0.0 {
+                duration / slowest
+            } else {
+                0.0
+            };
+            let font_size = MIN_FONT_SIZE + ratio * (MAX_FONT_SIZE - MIN_FONT_SIZE);
+            format!(
+                "label={}, fillcolor=\"0.08 {ratio:.3} 1.000\", fontsize={font_size:.1}",
+                quote(&format!(
+                    "{} {}\n{}",
+                    node.name,
+                    node.version,
+                    format_ms(duration)
+                ))
+            )
+        }
+        None => format!(
+            "label={}, fillcolor=\"#dddddd\", fontsize={MIN_FONT_SIZE:.1}",
+            quote(&format!("{} {}", node.name, node.version))
+        ),
+    };
+    let _ = writeln!(out, "{indent}{} [{attrs}];", quote(node.id.as_str()));
+}
+
+/// Quote a DOT ID, escaping quotes, backslashes and newlines.
+fn quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::{id, timed_graph};
+
+    #[test]
+    fn nodes_are_weighted_and_critical_edges_highlighted() {
+        let mut graph = timed_graph(
+            &[("a", 0.0, 2.0), ("b", 2.0, 1.0), ("c", 0.0, 0.5)],
+            &[("b", "a"), ("b", "c")],
+        );
+        graph.nodes.get_mut(&id("c")).unwrap().is_workspace_member = false;
+        let dot = generate_dot(&graph);
+        assert!(dot.contains(
+            "  \"a@0.1.0\" [label=\"a 0.1.0\\n2.00s\", fillcolor=\"0.08 1.000 1.000\", fontsize=24.0];"
+        ));
+        assert!(
+            dot.contains("\"b@0.1.0\" -> \"a@0.1.0\" [color=red, penwidth=3, tooltip=\"Normal\"];")
+        );
+        assert!(dot.contains("\"b@0.1.0\" -> \"c@0.1.0\" [tooltip=\"Normal\"];"));
+        assert!(dot.contains("subgraph cluster_dependencies {\n    label=\"dependencies\";\n    style=dashed;\n    \"c@0.1.0\""));
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt::Write;
+
+use crate::history::HistoryEntry;
+use crate::output::format_ms;
+use crate::output::text::{BOLD, TextStyle};
+
+const MIN_BAR_WIDTH: usize = 10;
+const BLUE: &str = "\x1b[34m";
+const DIM: &str = "\x1b[2m";
+
+/// List recorded runs, oldest first, each with a bar plotting its total time
+/// and, within it, its critical path (`█`) against the rest of the build (`░`).
+pub fn generate_text(entries: &[HistoryEntry], style: TextStyle) -> String {
+    let rows: Vec<[String; 5]> = entries
+        .iter()
+        .map(|e| {
+            let info = &e.build_info;
+            let commit = match &info.git_commit {
+                Some(commit) => {
+                    let short: String = commit.chars().take(10).collect();
+                    if info.git_dirty {
+                        format!("{short}*")
+                    } else {
+                        short
+                    }
+                }
+                None => "-".to_string(),
+            };
+            [
+                format_timestamp(info.timestamp),
+                commit,
+                format!("{} {}", info.mode, info.profile),
+                format_ms(e.total_ms.into()),
+                format_ms(e.critical_path_ms.into()),
+            ]
+        })
+        .collect();
+    let headers = ["Date (UTC)", "Commit", "Mode", "Total", "Critical path"];
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|c| {
+            rows.iter()
+                .map(|r| r[c].chars().count())
+                .chain([headers[c].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: &[String]| -> String {
+        let mut line = String::new();
+        for (c, cell) in cells.iter().enumerate() {
+            let pad = " ".repeat(widths[c] - cell.chars().count());
+            // Durations are right-aligned.
+            if c >= 3 {
+                let _ = write!(line, "  {pad}{cell}");
+            } else {
+                let _ = write!(line, "  {cell}{pad}");
+            }
+        }
+        line
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}",
+        style.paint(
+            BOLD,
+            &format!(
+                "Build history ({} run{})",
+                entries.len(),
+                if entries.len() == 1 { "" } else { "s" }
+            )
+        )
+    );
+    let header: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    let _ = writeln!(out, "{}", line(&header).trim_end());
+
+    let prefix_width: usize = widths.iter().map(|w| w + 2).sum();
+    let bar_width = style
+        .width
+        .saturating_sub(prefix_width + 2)
+        .max(MIN_BAR_WIDTH);
+    let slowest = entries
+        .iter()
+        .map(|e| f64::from(e.total_ms))
+        .fold(0.0, f64::max);
+    for (entry, row) in entries.iter().zip(&rows) {
+        let scale = |ms: f64| {
+            if slowest > 0.0 {
+                (ms / slowest * bar_width as f64).round() as usize
+            } else {
+                0
+            }
+        };
+        let total = scale(entry.total_ms.into());
+        let critical = scale(entry.critical_path_ms.into()).min(total);
+        let bar = format!(
+            "{}{}",
+            style.paint(BLUE, &"█".repeat(critical)),
+            style.paint(DIM, &"░".repeat(total - critical))
+        );
+        let _ = writeln!(out, "{}  {bar}", line(row));
+    }
+    out
+}
+
+/// `YYYY-MM-DD HH:MM` for seconds since the Unix epoch, in UTC.
+fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let minutes = secs % 86_400 / 60;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::BuildInfo;
+
+    fn entry(timestamp: u64, commit: &str, total: f64, critical_path: f64) -> HistoryEntry {
+        HistoryEntry {
+            path: Default::default(),
+            build_info: BuildInfo {
+                timestamp,
+                git_commit: Some(commit.to_string()),
+                profile: "dev".to_string(),
+                ..Default::default()
+            },
+            total_ms: total.into(),
+            critical_path_ms: critical_path.into(),
+        }
+    }
+
+    #[test]
+    fn runs_are_plotted_against_the_slowest() {
+        let style = TextStyle {
+            width: 85,
+            color: false,
+        };
+        let text = generate_text(
+            &[
+                entry(1_760_000_000, "0123456789abcdef", 20_000.0, 15_000.0),
+                entry(1_760_086_400, "fedcba9876543210", 10_000.0, 10_000.0),
+            ],
+            style,
+        );
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!("Build history (2 runs)", lines[0]);
+        assert_eq!(
+            "  Date (UTC)        Commit      Mode       Total  Critical path",
+            lines[1]
+        );
+        // 85 columns leave room for a 20-column bar.
+        assert_eq!(
+            format!(
+                "  2025-10-09 08:53  0123456789  check dev  20.0s          15.0s  {}{}",
+                "█".repeat(15),
+                "░".repeat(5)
+            ),
+            lines[2]
+        );
+        assert_eq!(
+            format!(
+                "  2025-10-10 08:53  fedcba9876  check dev  10.0s          10.0s  {}",
+                "█".repeat(10)
+            ),
+            lines[3]
+        );
+    }
+
+    #[test]
+    fn timestamps_are_formatted_in_utc() {
+        assert_eq!("1970-01-01 00:00", format_timestamp(0));
+        assert_eq!("2000-02-29 23:59", format_timestamp(951_868_799));
+    }
+}
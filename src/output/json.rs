@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{BuildGraph, Milliseconds};
+
+/// Version of the JSON report layout; bumped on incompatible changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonReport {
+    pub schema_version: u32,
+    /// Wall time of the build: when the last crate finished.
+    pub total_ms: Milliseconds,
+    #[serde(flatten)]
+    pub graph: BuildGraph,
+}
+
+pub fn generate_json(graph: &BuildGraph) -> anyhow::Result<String> {
+    let report = JsonReport {
+        schema_version: SCHEMA_VERSION,
+        total_ms: graph.total_ms(),
+        graph: graph.clone(),
+    };
+    Ok(serde_json::to_string_pretty(&report)?)
+}
+
+/// Read a report written by `--format json`.
+pub fn read_report(path: &Path) -> anyhow::Result<BuildGraph> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("failed to read {}: {e}", path.display()))?;
+    parse_report(&json).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))
+}
+
+fn parse_report(json: &str) -> anyhow::Result<BuildGraph> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let version = value
+        .get("schema_version")
+        .and_then(serde_json::Value::as_u64)
+        .ok_or_else(|| anyhow::anyhow!("not a cargo-goodtimes JSON report"))?;
+    anyhow::ensure!(
+        version == u64::from(SCHEMA_VERSION),
+        "unsupported report schema version {version} (expected {SCHEMA_VERSION})"
+    );
+    let report: JsonReport = serde_json::from_value(value)?;
+    Ok(report.graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::timed_graph;
+
+    #[test]
+    fn json_report_is_versioned() {
+        let graph = timed_graph(&[("a", 0.0, 1.0), ("b", 1.0, 0.5)], &[("b", "a")]);
+        let json = generate_json(&graph).unwrap();
+        let report: JsonReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(SCHEMA_VERSION, report.schema_version);
+        assert_eq!(Milliseconds::from(1500.0), report.total_ms);
+        assert_eq!(graph.critical_path, report.graph.critical_path);
+        assert_eq!(2, report.graph.nodes.len());
+    }
+
+    #[test]
+    fn reports_with_other_schema_versions_are_rejected() {
+        let graph = timed_graph(&[("a", 0.0, 1.0)], &[]);
+        let json = generate_json(&graph).unwrap();
+        assert_eq!(1, parse_report(&json).unwrap().nodes.len());
+
+        let future = json.replace("\"schema_version\": 1", "\"schema_version\": 99");
+        let err = parse_report(&future).unwrap_err().to_string();
+        assert!(err.contains("unsupported report schema version 99"));
+        assert!(parse_report("{}").is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt::Write;
+
+use crate::cargo_ops::build::critical_path_times;
+use crate::model::{BuildGraph, CrateNode, Milliseconds};
+use crate::output::{SummaryOptions, format_ms};
+
+/// How many crates with the most slack are listed.
+const SLACK_LIMIT: usize = 5;
+
+/// Summarize the build as GitHub-flavored Markdown, e.g. for
+/// `$GITHUB_STEP_SUMMARY` or a PR comment: totals, the critical path, the
+/// `top` slowest crates, the near-critical crates and the crates with the
+/// most slack.
+pub fn generate_markdown(graph: &BuildGraph, summary: SummaryOptions) -> String {
+    let total = f64::from(graph.total_ms());
+    let busy: f64 = graph
+        .nodes
+        .values()
+        .filter_map(|n| n.duration_ms)
+        .map(f64::from)
+        .sum();
+    let label = |node: &CrateNode| format!("`{}` {}", node.name, node.version);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "## Build times (`cargo {}`)\n", graph.build_info.mode);
+    let _ = write!(
+        out,
+        "**Total:** {} · **Critical path:** {} ({} crates)",
+        format_ms(total),
+        format_ms(graph.critical_path_ms.into()),
+        graph.critical_path.len()
+    );
+    if total > 0.0 {
+        let _ = write!(out, " · **Parallelism:** {:.1}x", busy / total);
+    }
+    let _ = writeln!(out);
+
+    let _ = writeln!(out, "\n### Critical path\n");
+    let _ = writeln!(out, "| Crate | Time on path | Cumulative |");
+    let _ = writeln!(out, "| --- | ---: | ---: |");
+    let mut cumulative = 0.0;
+    for (id, time) in critical_path_times(graph) {
+        let Some(node) = graph.nodes.get(&id) else {
+            continue;
+        };
+        cumulative += f64::from(time);
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} |",
+            label(node),
+            format_ms(time.into()),
+            format_ms(cumulative)
+        );
+    }
+
+    if graph.longest_chains.len() > 1 {
+        let _ = writeln!(out, "\n### Longest dependency chains\n");
+        let _ = writeln!(
+            out,
+            "When the critical path is shortened, the next longest chain takes over.\n"
+        );
+        let _ = writeln!(out, "| # | Length | Crates |");
+        let _ = writeln!(out, "| ---: | ---: | --- |");
+        for (i, chain) in graph.longest_chains.iter().enumerate() {
+            let names: Vec<String> = chain
+                .crates
+                .iter()
+                .filter_map(|id| Some(format!("`{}`", graph.nodes.get(id)?.name)))
+                .collect();
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                i + 1,
+                format_ms(chain.length_ms.into()),
+                names.join(" → ")
+            );
+        }
+    }
+
+    let mut slowest: Vec<(&CrateNode, f64)> = graph
+        .nodes
+        .values()
+        .filter_map(|n| Some((n, f64::from(n.duration_ms?))))
+        .collect();
+    slowest.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.id.cmp(&b.0.id)));
+    let _ = writeln!(out, "\n### Slowest crates\n");
+    let _ = writeln!(out, "| Crate | Duration | Share of total |");
+    let _ = writeln!(out, "| --- | ---: | ---: |");
+    for (node, duration) in slowest.iter().take(summary.top) {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {:.0}% |",
+            label(node),
+            format_ms(*duration),
+            100.0 * duration / total.max(1.0)
+        );
+    }
+
+    let near_critical = graph.near_critical(summary.near_critical);
+    if !near_critical.is_empty() {
+        let _ = writeln!(out, "\n### Near-critical crates\n");
+        let _ = writeln!(
+            out,
+            "These crates are within {}% of the critical path's length of becoming critical.\n",
+            summary.near_critical
+        );
+        let _ = writeln!(out, "| Crate | Earliest start | Latest start | Slack |");
+        let _ = writeln!(out, "| --- | ---: | ---: | ---: |");
+        for node in near_critical {
+            let ms = |ms: Option<Milliseconds>| format_ms(ms.map_or(0.0, f64::from));
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                label(node),
+                ms(node.earliest_start_ms),
+                ms(node.latest_start_ms),
+                ms(node.slack_ms)
+            );
+        }
+    }
+
+    let mut slack: Vec<(&CrateNode, f64)> = graph
+        .nodes
+        .values()
+        .filter(|n| n.duration_ms.is_some())
+        .filter_map(|n| Some((n, f64::from(n.slack_ms?))))
+        .filter(|&(_, slack)| slack >= 1.0)
+        .collect();
+    slack.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.id.cmp(&b.0.id)));
+    if !slack.is_empty() {
+        let _ = writeln!(out, "\n### Most slack\n");
+        let _ = writeln!(
+            out,
+            "These crates could take this much longer without slowing down the build.\n"
+        );
+        let _ = writeln!(out, "| Crate | Duration | Slack |");
+        let _ = writeln!(out, "| --- | ---: | ---: |");
+        for (node, slack) in slack.iter().take(SLACK_LIMIT) {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                label(node),
+                format_ms(node.duration_ms.map_or(0.0, f64::from)),
+                format_ms(*slack)
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::timed_graph;
+
+    #[test]
+    fn summary_tables() {
+        let graph = timed_graph(
+            &[("a", 0.0, 1.0), ("b", 1.0, 2.0), ("c", 1.0, 0.5)],
+            &[("b", "a"), ("c", "a")],
+        );
+        let summary = SummaryOptions {
+            top: 2,
+            near_critical: 10.0,
+        };
+        let md = generate_markdown(&graph, summary);
+        assert!(md.contains("**Total:** 3.00s · **Critical path:** 3.00s (2 crates)"));
+        assert!(md.contains("| `a` 0.1.0 | 1.00s | 1.00s |\n| `b` 0.1.0 | 2.00s | 3.00s |"));
+        // Only the two slowest crates are listed.
+        assert!(md.contains("| `b` 0.1.0 | 2.00s | 67% |\n| `a` 0.1.0 | 1.00s | 33% |\n\n"));
+        assert!(md.contains("| `c` 0.1.0 | 500ms | 1.50s |"));
+        assert!(!md.contains("Near-critical"));
+        assert!(md.contains("| 1 | 3.00s | `a` → `b` |\n| 2 | 1.50s | `a` → `c` |\n"));
+        let summary = SummaryOptions {
+            top: 2,
+            near_critical: 50.0,
+        };
+        assert!(
+            generate_markdown(&graph, summary).contains("| `c` 0.1.0 | 1.00s | 2.50s | 1.50s |")
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::model::{BuildGraph, CrateNode};
+use crate::output::{escape, format_ms};
+
+const LABEL_WIDTH: f64 = 200.0;
+const CHART_WIDTH: f64 = 800.0;
+const PADDING: f64 = 16.0;
+const HEADER_HEIGHT: f64 = 32.0;
+const AXIS_HEIGHT: f64 = 24.0;
+const ROW_HEIGHT: f64 = 18.0;
+const BAR_HEIGHT: f64 = 12.0;
+
+// Same palette as the HTML report.
+const COLOR_BACKGROUND: &str = "#0f1117";
+const COLOR_GRID: &str = "#2a2d3a";
+const COLOR_TEXT: &str = "#e1e4ed";
+const COLOR_TEXT_SECONDARY: &str = "#8b8fa3";
+const COLOR_BAR: &str = "#6c7be9";
+const COLOR_BAR_DEP: &str = "#5c5f73";
+const COLOR_CRITICAL: &str = "#e8954a";
+
+/// Render the Gantt chart as a standalone SVG: one bar per timed crate in
+/// start order, critical-path crates outlined, with a time axis on top.
+pub fn generate_svg(graph: &BuildGraph) -> anyhow::Result<String> {
+    let mut rows: Vec<(&CrateNode, f64, f64)> = graph
+        .nodes
+        .values()
+        .filter_map(|n| Some((n, f64::from(n.start_ms?), f64::from(n.duration_ms?))))
+        .collect();
+    rows.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.id.cmp(&b.0.id)));
+
+    let total = f64::from(graph.total_ms()).max(1.0);
+    let critical: HashSet<_> = graph.critical_path.iter().collect();
+    let scale = CHART_WIDTH / total;
+    let chart_x = PADDING + LABEL_WIDTH;
+    let chart_y = PADDING + HEADER_HEIGHT + AXIS_HEIGHT;
+    let width = chart_x + CHART_WIDTH + PADDING;
+    let height = chart_y + rows.len() as f64 * ROW_HEIGHT + PADDING;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="ui-monospace, SFMono-Regular, Menlo, monospace" font-size="11">"#
+    )?;
+    writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{COLOR_BACKGROUND}"/>"#
+    )?;
+    writeln!(
+        svg,
+        r#"<text x="{PADDING}" y="{}" fill="{COLOR_TEXT}" font-size="14">cargo {} — {} total, critical path {}</text>"#,
+        PADDING + 16.0,
+        graph.build_info.mode,
+        format_ms(total),
+        format_ms(f64::from(graph.critical_path_ms)),
+    )?;
+
+    // Time axis with gridlines.
+    let step = tick_step(total);
+    let mut tick = 0.0;
+    while tick <= total {
+        let x = chart_x + tick * scale;
+        writeln!(
+            svg,
+            r#"<line x1="{x:.1}" y1="{:.1}" x2="{x:.1}" y2="{height:.1}" stroke="{COLOR_GRID}"/>"#,
+            chart_y - 4.0,
+        )?;
+        writeln!(
+            svg,
+            r#"<text x="{x:.1}" y="{:.1}" fill="{COLOR_TEXT_SECONDARY}" text-anchor="middle">{}</text>"#,
+            chart_y - 8.0,
+            format_ms(tick),
+        )?;
+        tick += step;
+    }
+
+    for (i, (node, start, duration)) in rows.iter().enumerate() {
+        let y = chart_y + i as f64 * ROW_HEIGHT;
+        let is_critical = critical.contains(&node.id);
+        writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" fill="{COLOR_TEXT}" text-anchor="end">{}</text>"#,
+            chart_x - 8.0,
+            y + ROW_HEIGHT / 2.0 + 4.0,
+            escape(&node.name),
+        )?;
+        let fill = if node.is_workspace_member {
+            COLOR_BAR
+        } else {
+            COLOR_BAR_DEP
+        };
+        let stroke = if is_critical {
+            format!(r#" stroke="{COLOR_CRITICAL}" stroke-width="2""#)
+        } else {
+            String::new()
+        };
+        writeln!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{BAR_HEIGHT}" rx="2" fill="{fill}"{stroke}><title>{} {} ({})</title></rect>"#,
+            chart_x + start * scale,
+            y + (ROW_HEIGHT - BAR_HEIGHT) / 2.0,
+            (duration * scale).max(1.0),
+            escape(&node.name),
+            escape(&node.version),
+            format_ms(*duration),
+        )?;
+
+        // Error bar: where the crate ended over repeated runs.
+        if let Some(stats) = node.duration_stats.filter(|s| s.max > s.min) {
+            let x1 = chart_x + (start + f64::from(stats.min)) * scale;
+            let x2 = chart_x + (start + f64::from(stats.max)) * scale;
+            let mid = y + ROW_HEIGHT / 2.0;
+            let (top, bottom) = (mid - BAR_HEIGHT / 3.0, mid + BAR_HEIGHT / 3.0);
+            writeln!(
+                svg,
+                r#"<path d="M{x1:.1} {top:.1}V{bottom:.1}M{x1:.1} {mid:.1}H{x2:.1}M{x2:.1} {top:.1}V{bottom:.1}" stroke="{COLOR_TEXT}" fill="none"/>"#,
+            )?;
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// A round axis interval (1, 2 or 5 × 10ⁿ ms) giving at most ~10 ticks.
+fn tick_step(total_ms: f64) -> f64 {
+    let rough = total_ms / 10.0;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= rough)
+        .unwrap_or(10.0 * magnitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::timed_graph;
+
+    #[test]
+    fn bars_follow_timings() {
+        let graph = timed_graph(&[("a", 0.0, 1.0), ("b", 1.0, 1.0)], &[("b", "a")]);
+        let svg = generate_svg(&graph).unwrap();
+        // Two seconds span the full chart width, so each crate gets half.
+        let bar = |name: &str| {
+            let title = format!("<title>{name} 0.1.0");
+            svg.lines()
+                .find(|l| l.contains(&title))
+                .unwrap()
+                .to_string()
+        };
+        assert!(bar("a").contains(r#"x="216.0""#));
+        assert!(bar("b").contains(r#"x="616.0""#));
+        assert!(bar("b").contains(r#"width="400.0""#));
+        assert!(bar("b").contains(COLOR_CRITICAL));
+        assert!(svg.contains(">2.00s</text>"));
+    }
+
+    #[test]
+    fn tick_steps_are_round() {
+        assert_eq!(1000.0, tick_step(9_000.0));
+        assert_eq!(2000.0, tick_step(15_000.0));
+        assert_eq!(5000.0, tick_step(45_000.0));
+    }
+}
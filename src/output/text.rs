@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::model::{BuildGraph, CrateNode};
+use crate::output::{SummaryOptions, format_ms};
+
+const MAX_NAME_WIDTH: usize = 28;
+const MIN_BAR_WIDTH: usize = 10;
+
+pub(crate) const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const BLUE: &str = "\x1b[34m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// How the text report is laid out.
+#[derive(Debug, Clone, Copy)]
+pub struct TextStyle {
+    /// Total line width, in columns.
+    pub width: usize,
+    /// Use ANSI colors.
+    pub color: bool,
+}
+
+impl TextStyle {
+    /// Fit the terminal when printing to one, with colors unless `NO_COLOR`
+    /// is set. Otherwise plain text, `$COLUMNS` (or 100) wide.
+    pub fn detect(to_terminal: bool) -> Self {
+        let terminal_width = to_terminal
+            .then(|| terminal_size::terminal_size().map(|(w, _)| w.0 as usize))
+            .flatten();
+        let width = terminal_width
+            .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+            .unwrap_or(100);
+        TextStyle {
+            width,
+            color: to_terminal && std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+
+    pub(crate) fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("{code}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Summarize the build for a terminal: total wall time, critical path length
+/// and parallelism, followed by a Gantt chart of the `top` slowest crates and
+/// the near-critical crates.
+pub fn generate_text(graph: &BuildGraph, summary: SummaryOptions, style: TextStyle) -> String {
+    let total = f64::from(graph.total_ms());
+    let critical_path = f64::from(graph.critical_path_ms);
+    let busy: f64 = graph
+        .nodes
+        .values()
+        .filter_map(|n| n.duration_ms)
+        .map(f64::from)
+        .sum();
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}",
+        style.paint(BOLD, &format!("cargo {}", graph.build_info.mode))
+    );
+    let _ = writeln!(out, "  total time     {}", format_ms(total));
+    let _ = writeln!(
+        out,
+        "  critical path  {} ({} crates)",
+        format_ms(critical_path),
+        graph.critical_path.len()
+    );
+    if total > 0.0 {
+        let _ = writeln!(out, "  parallelism    {:.1}x", busy / total);
+    }
+
+    let mut slowest: Vec<(&CrateNode, f64, f64)> = graph
+        .nodes
+        .values()
+        .filter_map(|n| Some((n, f64::from(n.start_ms?), f64::from(n.duration_ms?))))
+        .collect();
+    if slowest.is_empty() || total <= 0.0 {
+        return out;
+    }
+    slowest.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.id.cmp(&b.0.id)));
+    let shown = slowest.len().min(summary.top);
+    let mut rows = slowest[..shown].to_vec();
+    rows.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.id.cmp(&b.0.id)));
+
+    let critical: HashSet<_> = graph.critical_path.iter().collect();
+    let name_width = rows
+        .iter()
+        .map(|(n, _, _)| n.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(MAX_NAME_WIDTH);
+    // "* name  duration  |bar|"
+    let duration_width = 8;
+    let bar_width = style
+        .width
+        .saturating_sub(name_width + duration_width + 8)
+        .max(MIN_BAR_WIDTH);
+    let scale = bar_width as f64 / total;
+
+    let _ = writeln!(out);
+    for (node, start, duration) in rows {
+        let from = ((start * scale).round() as usize).min(bar_width - 1);
+        let to = (((start + duration) * scale).round() as usize).clamp(from + 1, bar_width);
+        let bar = format!(
+            "{}{}{}",
+            " ".repeat(from),
+            "█".repeat(to - from),
+            " ".repeat(bar_width - to)
+        );
+
+        let is_critical = critical.contains(&node.id);
+        let marker = if is_critical { "*" } else { " " };
+        let bar_color = if is_critical {
+            YELLOW
+        } else if node.is_workspace_member {
+            BLUE
+        } else {
+            DIM
+        };
+        let _ = writeln!(
+            out,
+            "{} {:<name_width$}  {:>duration_width$}  |{}|",
+            style.paint(YELLOW, marker),
+            truncate(&node.name, name_width),
+            format_ms(duration),
+            style.paint(bar_color, &bar),
+        );
+    }
+
+    // Time axis under the bars.
+    let axis_indent = name_width + duration_width + 7;
+    let end_label = format_ms(total);
+    let _ = writeln!(
+        out,
+        "{:axis_indent$}0{:>width$}",
+        "",
+        end_label,
+        width = bar_width - 1
+    );
+    let _ = writeln!(
+        out,
+        "\n{} slowest of {} crates, {} on the critical path",
+        shown,
+        slowest.len(),
+        style.paint(YELLOW, "*")
+    );
+
+    if graph.longest_chains.len() > 1 {
+        let _ = writeln!(out, "\nLongest dependency chains");
+        for (i, chain) in graph.longest_chains.iter().enumerate() {
+            let names: Vec<&str> = chain
+                .crates
+                .iter()
+                .filter_map(|id| Some(graph.nodes.get(id)?.name.as_str()))
+                .collect();
+            let _ = writeln!(
+                out,
+                "  {}. {:>duration_width$}  {}",
+                i + 1,
+                format_ms(chain.length_ms.into()),
+                names.join(" → ")
+            );
+        }
+    }
+
+    let near_critical = graph.near_critical(summary.near_critical);
+    if !near_critical.is_empty() {
+        let _ = writeln!(
+            out,
+            "\nNear-critical (slack within {}% of the critical path)",
+            summary.near_critical
+        );
+        let name_width = near_critical
+            .iter()
+            .map(|n| n.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(MAX_NAME_WIDTH);
+        for node in near_critical {
+            let _ = writeln!(
+                out,
+                "  {:<name_width$}  {:>duration_width$} slack",
+                truncate(&node.name, name_width),
+                format_ms(node.slack_ms.map_or(0.0, f64::from)),
+            );
+        }
+    }
+    out
+}
+
+fn truncate(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        return name.to_string();
+    }
+    let mut short: String = name.chars().take(width.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::timed_graph;
+
+    #[test]
+    fn chart_lists_slowest_crates_in_start_order() {
+        let graph = timed_graph(
+            &[("a", 0.0, 1.0), ("b", 1.0, 3.0), ("c", 0.0, 0.5)],
+            &[("b", "a")],
+        );
+        let style = TextStyle {
+            width: 60,
+            color: false,
+        };
+        let summary = SummaryOptions {
+            top: 2,
+            near_critical: 20.0,
+        };
+        let text = generate_text(&graph, summary, style);
+        assert!(text.contains("total time     4.00s"));
+        assert!(text.contains("critical path  4.00s (2 crates)"));
+        assert!(text.contains("parallelism    1.1x"));
+
+        let rows: Vec<&str> = text.lines().filter(|l| l.contains('|')).collect();
+        assert_eq!(2, rows.len());
+        assert!(rows[0].starts_with("* a "));
+        assert!(rows[1].starts_with("* b "));
+        assert!(rows.iter().all(|r| r.chars().count() == 60));
+        assert!(!text.contains('\x1b'));
+        // `c` could take 3.5s longer, too much to be near-critical.
+        assert!(!text.contains("Near-critical"));
+        assert!(text.contains(
+            "Longest dependency chains\n\
+             \x20 1.    4.00s  a → b\n\
+             \x20 2.    500ms  c\n"
+        ));
+    }
+
+    #[test]
+    fn near_critical_crates_are_listed() {
+        let graph = timed_graph(
+            &[("a", 0.0, 2.0), ("b", 0.0, 1.8), ("c", 2.0, 1.0)],
+            &[("c", "a"), ("c", "b")],
+        );
+        let style = TextStyle {
+            width: 60,
+            color: false,
+        };
+        let text = generate_text(&graph, SummaryOptions::default(), style);
+        assert!(text.ends_with(
+            "Near-critical (slack within 10% of the critical path)\n\
+             \x20 b     200ms slack\n"
+        ));
+    }
+}
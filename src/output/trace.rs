@@ -0,0 +1,269 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::model::{BuildGraph, CrateId, UnitKind};
+
+const PID: u32 = 1;
+
+/// A Chrome Trace Event, as understood by `chrome://tracing` and Perfetto.
+#[derive(Debug, Serialize)]
+struct TraceEvent {
+    name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    cat: String,
+    ph: &'static str,
+    /// Microseconds from build start.
+    ts: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    pid: u32,
+    tid: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bp: Option<&'static str>,
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    args: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace {
+    trace_events: Vec<TraceEvent>,
+    display_time_unit: &'static str,
+}
+
+/// A timed crate, in microseconds.
+struct Slice<'a> {
+    id: &'a CrateId,
+    start: f64,
+    end: f64,
+}
+
+/// Convert crate timings into Chrome trace-event JSON. Crates are packed onto
+/// simulated worker threads, critical-path crates are flagged in their args
+/// and dependency edges become flow events.
+pub fn generate_trace(graph: &BuildGraph) -> anyhow::Result<String> {
+    let mut slices: Vec<Slice> = graph
+        .nodes
+        .values()
+        .filter_map(|n| {
+            let start = f64::from(n.start_ms?) * 1000.0;
+            let end = start + f64::from(n.duration_ms?) * 1000.0;
+            Some(Slice {
+                id: &n.id,
+                start,
+                end,
+            })
+        })
+        .collect();
+    slices.sort_by(|a, b| a.start.total_cmp(&b.start).then_with(|| a.id.cmp(b.id)));
+
+    let lanes = pack_lanes(&slices);
+    let lane_of: HashMap<&CrateId, usize> = slices
+        .iter()
+        .zip(&lanes)
+        .map(|(slice, &lane)| (slice.id, lane))
+        .collect();
+    let slice_of: HashMap<&CrateId, &Slice> = slices.iter().map(|s| (s.id, s)).collect();
+    let critical: HashSet<&CrateId> = graph.critical_path.iter().collect();
+
+    let mut events = vec![TraceEvent {
+        name: "process_name".to_string(),
+        cat: String::new(),
+        ph: "M",
+        ts: 0.0,
+        dur: None,
+        pid: PID,
+        tid: 0,
+        id: None,
+        bp: None,
+        args: serde_json::json!({ "name": format!("cargo {}", graph.build_info.mode) }),
+    }];
+
+    let lane_count = lanes.iter().max().map_or(0, |max| max + 1);
+    for lane in 0..lane_count {
+        events.push(TraceEvent {
+            name: "thread_name".to_string(),
+            cat: String::new(),
+            ph: "M",
+            ts: 0.0,
+            dur: None,
+            pid: PID,
+            tid: lane,
+            id: None,
+            bp: None,
+            args: serde_json::json!({ "name": format!("worker {lane}") }),
+        });
+    }
+
+    for (slice, &lane) in slices.iter().zip(&lanes) {
+        let node = &graph.nodes[slice.id];
+        let is_critical = critical.contains(slice.id);
+        events.push(TraceEvent {
+            name: node.name.clone(),
+            cat: if is_critical {
+                "crate,critical"
+            } else {
+                "crate"
+            }
+            .to_string(),
+            ph: "X",
+            ts: slice.start,
+            dur: Some(slice.end - slice.start),
+            pid: PID,
+            tid: lane,
+            id: None,
+            bp: None,
+            args: serde_json::json!({
+                "id": node.id,
+                "version": node.version,
+                "critical": is_critical,
+                "workspace_member": node.is_workspace_member,
+                "fresh": node.fresh,
+                "units": node.units,
+            }),
+        });
+    }
+
+    // Flow arrows from when each dependency was ready to its dependent's
+    // start: its metadata for pipelined libraries, otherwise its end.
+    let mut edges: Vec<_> = graph.edges.iter().collect();
+    edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+    for (flow_id, edge) in edges.into_iter().enumerate() {
+        let (Some(dep), Some(dependent)) = (slice_of.get(&edge.to), slice_of.get(&edge.from))
+        else {
+            continue;
+        };
+        let (dep_node, dependent_node) = (&graph.nodes[&edge.to], &graph.nodes[&edge.from]);
+        let pipelined =
+            edge.is_normal() && dependent_node.units.iter().any(|u| u.kind == UnitKind::Lib);
+        let ready = match dep_node.rmeta_ms {
+            Some(rmeta) if pipelined => dep.start + f64::from(rmeta) * 1000.0,
+            _ => dep.end,
+        };
+        let name = format!("{} → {}", dep_node.name, dependent_node.name);
+        events.push(TraceEvent {
+            name: name.clone(),
+            cat: "dependency".to_string(),
+            ph: "s",
+            // Just inside the dependency's slice, so the flow binds to it.
+            ts: (ready - 1.0).max(dep.start),
+            dur: None,
+            pid: PID,
+            tid: lane_of[dep.id],
+            id: Some(flow_id),
+            bp: None,
+            args: serde_json::Value::Null,
+        });
+        events.push(TraceEvent {
+            name,
+            cat: "dependency".to_string(),
+            ph: "f",
+            ts: dependent.start,
+            dur: None,
+            pid: PID,
+            tid: lane_of[dependent.id],
+            id: Some(flow_id),
+            bp: Some("e"),
+            args: serde_json::Value::Null,
+        });
+    }
+
+    let trace = Trace {
+        trace_events: events,
+        display_time_unit: "ms",
+    };
+    Ok(serde_json::to_string(&trace)?)
+}
+
+/// Assign each slice (sorted by start) to the lowest-numbered lane that is
+/// free by the time it starts, using as few lanes as there were concurrent
+/// crates.
+fn pack_lanes(slices: &[Slice]) -> Vec<usize> {
+    let mut lane_ends: Vec<f64> = Vec::new();
+    slices
+        .iter()
+        .map(
+            |slice| match lane_ends.iter().position(|&end| end <= slice.start) {
+                Some(lane) => {
+                    lane_ends[lane] = slice.end;
+                    lane
+                }
+                None => {
+                    lane_ends.push(slice.end);
+                    lane_ends.len() - 1
+                }
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cargo_ops::build::apply_timings;
+    use crate::model::fixtures::{graph, timed_graph, unit};
+
+    #[test]
+    fn overlapping_crates_get_separate_threads() {
+        let graph = timed_graph(
+            &[("a", 0.0, 1.0), ("b", 0.5, 1.0), ("c", 1.0, 1.0)],
+            &[("c", "a")],
+        );
+        let trace: serde_json::Value =
+            serde_json::from_str(&generate_trace(&graph).unwrap()).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        let tid = |name: &str| {
+            events
+                .iter()
+                .find(|e| e["ph"] == "X" && e["name"] == name)
+                .unwrap()["tid"]
+                .as_u64()
+                .unwrap()
+        };
+        assert_eq!(0, tid("a"));
+        assert_eq!(1, tid("b"));
+        // c starts when a ends, so it reuses a's thread.
+        assert_eq!(0, tid("c"));
+
+        let critical = events
+            .iter()
+            .find(|e| e["ph"] == "X" && e["name"] == "c")
+            .unwrap();
+        assert_eq!(true, critical["args"]["critical"]);
+        assert_eq!(
+            2,
+            events.iter().filter(|e| e["cat"] == "dependency").count()
+        );
+    }
+
+    #[test]
+    fn pipelined_flows_start_at_metadata() {
+        let mut g = graph(
+            &["a", "b", "c"],
+            &[("b", "a", "Normal"), ("c", "a", "Normal")],
+        );
+        let mut a = unit("a", "", 0.0, 1.0);
+        a.rmeta_time = Some(0.4);
+        apply_timings(
+            &mut g,
+            &[a, unit("b", "", 0.4, 1.0), unit("c", "bin \"c\"", 1.0, 1.0)],
+        );
+        let trace: serde_json::Value = serde_json::from_str(&generate_trace(&g).unwrap()).unwrap();
+        let flow_start = |name: &str| {
+            trace["traceEvents"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|e| e["ph"] == "s" && e["name"] == name)
+                .unwrap()["ts"]
+                .as_f64()
+                .unwrap()
+        };
+        // b's library only waits for a's metadata; c's binary links all of a.
+        assert_eq!(399_999.0, flow_start("a → b"));
+        assert_eq!(999_999.0, flow_start("a → c"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt::Write;
+
+use crate::diff::BuildDiff;
+use crate::model::BuildGraph;
+use crate::output::diff::{Table, changes, critical_paths, format_delta, structure, summary};
+use crate::output::format_ms;
+use crate::output::text::{BOLD, TextStyle};
+use crate::whatif::{Candidate, DurationOverride};
+
+/// Compare a build against a what-if variant of it: the dependency changes,
+/// predicted totals, changed compile times and the critical paths side by
+/// side.
+pub fn generate_text(diff: &BuildDiff, jobs: usize, style: TextStyle) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}",
+        style.paint(BOLD, &format!("What if (simulated with {jobs} jobs)"))
+    );
+    for (heading, items) in structure(diff) {
+        let _ = writeln!(out, "\n{heading}");
+        for item in items {
+            let _ = writeln!(out, "  {item}");
+        }
+    }
+    out.push('\n');
+    out.push_str(&summary(diff).text(style));
+
+    let changes = changes(diff, usize::MAX);
+    if !changes.rows.is_empty() {
+        let _ = writeln!(out, "\nChanged compile times");
+        out.push_str(&changes.text(style));
+    }
+    out.push('\n');
+    out.push_str(&critical_paths(diff).text(style));
+    out
+}
+
+/// The `top` crates whose changed compile time would save the most.
+pub fn generate_ranking_text(
+    graph: &BuildGraph,
+    candidates: &[Candidate],
+    duration: DurationOverride,
+    jobs: usize,
+    top: usize,
+    style: TextStyle,
+) -> String {
+    let mut table = Table::new(
+        vec!["Crate", "Time", "Wall time", "Critical path"],
+        vec![false, true, true, true],
+    );
+    for candidate in candidates.iter().take(top) {
+        let node = &graph.nodes[&candidate.id];
+        table.rows.push(vec![
+            format!("{} {}", node.name, node.version),
+            format_ms(candidate.duration_ms.into()),
+            format_delta(-candidate.gain_ms),
+            format_delta(-candidate.critical_path_gain_ms),
+        ]);
+    }
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}",
+        style.paint(
+            BOLD,
+            &format!("Predicted effect of {duration} compile time (simulated with {jobs} jobs)")
+        )
+    );
+    out.push_str(&table.text(style));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::timed_graph;
+    use crate::whatif::rank;
+
+    #[test]
+    fn ranking_lists_largest_gains_first() {
+        let g = timed_graph(
+            &[("a", 0.0, 2.0), ("b", 0.0, 1.0), ("c", 2.0, 1.0)],
+            &[("c", "a"), ("c", "b")],
+        );
+        let style = TextStyle {
+            width: 80,
+            color: false,
+        };
+        let text = generate_ranking_text(
+            &g,
+            &rank(&g, DurationOverride::Scale(0.5), 2),
+            DurationOverride::Scale(0.5),
+            2,
+            2,
+            style,
+        );
+        assert_eq!(
+            "Predicted effect of 0.5x compile time (simulated with 2 jobs)\n\
+             \x20 Crate     Time  Wall time  Critical path\n\
+             \x20 a 0.1.0  2.00s     -1.00s         -1.00s\n\
+             \x20 c 0.1.0  1.00s     -500ms         -500ms\n",
+            text
+        );
+    }
+}
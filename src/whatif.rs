@@ -0,0 +1,538 @@
+use std::collections::HashSet;
+
+use anyhow::Context;
+
+use crate::cargo_ops::{build, simulate};
+use crate::model::{BuildGraph, CrateId, DepEdge, Milliseconds, parse_duration_ms};
+
+/// A dependency edge given as `dependent~dependency`, each side a crate ID
+/// (`name@version`) or an unambiguous crate name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeSpec {
+    pub from: String,
+    pub to: String,
+}
+
+impl std::str::FromStr for EdgeSpec {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once('~') {
+            Some((from, to)) if !from.is_empty() && !to.is_empty() => Ok(EdgeSpec {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            _ => Err(format!(
+                "invalid edge `{value}`: expected `dependent~dependency`, e.g. `a@1.0.0~b@2.1.0`"
+            )),
+        }
+    }
+}
+
+/// A crate's compile time in a what-if: scaled (`0.5x`) or set (`800ms`, `2s`).
+/// Like the measured compile time, it covers the crate's own targets; its
+/// build script keeps its measured time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DurationOverride {
+    Scale(f64),
+    Millis(f64),
+}
+
+impl DurationOverride {
+    /// The factor taking a crate's measured compile time to the new one, if
+    /// there is one: a crate measured at zero can't be stretched.
+    fn factor(self, measured_ms: f64) -> Option<f64> {
+        match self {
+            DurationOverride::Scale(factor) => Some(factor),
+            DurationOverride::Millis(ms) => (measured_ms > 0.0).then(|| ms / measured_ms),
+        }
+    }
+}
+
+impl std::fmt::Display for DurationOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DurationOverride::Scale(factor) => write!(f, "{factor}x"),
+            DurationOverride::Millis(ms) => write!(f, "{ms}ms"),
+        }
+    }
+}
+
+impl std::str::FromStr for DurationOverride {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let duration = match value.strip_suffix('x') {
+            Some(factor) => factor
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|f| *f >= 0.0)
+                .map(DurationOverride::Scale),
+            None => parse_duration_ms(value).map(DurationOverride::Millis),
+        };
+        duration.ok_or_else(|| {
+            format!(
+                "invalid compile time `{value}`: expected a factor (0.5x) or a duration (800ms, 2s)"
+            )
+        })
+    }
+}
+
+/// A compile time override given as `crate:time`, e.g. `serde_json:0.5x`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationSpec {
+    pub krate: String,
+    pub duration: DurationOverride,
+}
+
+impl std::str::FromStr for DurationSpec {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.rsplit_once(':') {
+            Some((krate, duration)) if !krate.is_empty() => Ok(DurationSpec {
+                krate: krate.to_string(),
+                duration: duration.parse()?,
+            }),
+            _ => Err(format!(
+                "invalid compile time `{value}`: expected `crate:time`, e.g. `serde_json:0.5x`"
+            )),
+        }
+    }
+}
+
+/// Dependency edges to remove and add and compile times to change, as in the
+/// report's URL.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scenario {
+    pub remove: Vec<EdgeSpec>,
+    pub add: Vec<EdgeSpec>,
+    pub durations: Vec<DurationSpec>,
+}
+
+impl Scenario {
+    /// Parse the `rm=`, `add=` and `dur=` parameters of a report URL's hash,
+    /// e.g. `#sel=a@1.0.0&rm=a@1.0.0~b@2.1.0&dur=c@0.3.0:0.5x`. Takes the
+    /// whole URL, the hash or just its parameters; other parameters are
+    /// ignored.
+    pub fn from_hash(hash: &str) -> anyhow::Result<Self> {
+        let params = hash.rsplit_once('#').map_or(hash, |(_, params)| params);
+        let mut scenario = Scenario::default();
+        for param in params.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let items = value.split(',').filter(|item| !item.is_empty());
+            match key {
+                "rm" => scenario.remove.extend(parse_all(items)?),
+                "add" => scenario.add.extend(parse_all(items)?),
+                "dur" => scenario.durations.extend(parse_all(items)?),
+                _ => {}
+            }
+        }
+        Ok(scenario)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remove.is_empty() && self.add.is_empty() && self.durations.is_empty()
+    }
+}
+
+fn parse_all<'a, T>(items: impl Iterator<Item = &'a str>) -> anyhow::Result<Vec<T>>
+where
+    T: std::str::FromStr<Err = String>,
+{
+    items
+        .map(|item| item.parse().map_err(anyhow::Error::msg))
+        .collect()
+}
+
+/// Apply the scenario to a copy of the graph and recompute its critical
+/// path. Edges are removed before any are added, and an added edge that
+/// would make a crate depend on itself is rejected. A changed compile time
+/// scales all of the crate's units alike.
+pub fn apply(graph: &BuildGraph, scenario: &Scenario) -> anyhow::Result<BuildGraph> {
+    let mut graph = graph.clone();
+    for spec in &scenario.remove {
+        let (from, to) = resolve_edge(&graph, spec)?;
+        let before = graph.edges.len();
+        graph.edges.retain(|e| !(e.from == from && e.to == to));
+        anyhow::ensure!(
+            graph.edges.len() < before,
+            "{} does not depend on {}",
+            from.as_str(),
+            to.as_str()
+        );
+    }
+    for spec in &scenario.add {
+        let (from, to) = resolve_edge(&graph, spec)?;
+        anyhow::ensure!(
+            !graph.edges.iter().any(|e| e.from == from && e.to == to),
+            "{} already depends on {}",
+            from.as_str(),
+            to.as_str()
+        );
+        anyhow::ensure!(
+            !depends_on(&graph, &to, &from),
+            "{} → {} would create a cycle: {} depends on {}",
+            from.as_str(),
+            to.as_str(),
+            to.as_str(),
+            from.as_str()
+        );
+        graph.edges.push(DepEdge {
+            from,
+            to,
+            dep_kinds: Vec::new(),
+        });
+    }
+    // A later compile time for the same crate replaces an earlier one, as in
+    // the report, rather than compounding with it.
+    let mut durations: Vec<(CrateId, DurationOverride)> = Vec::new();
+    for spec in &scenario.durations {
+        let id = resolve_crate(&graph, &spec.krate)?;
+        durations.retain(|(other, _)| *other != id);
+        durations.push((id, spec.duration));
+    }
+    for (id, duration) in durations {
+        let node = graph.nodes.get_mut(&id).expect("resolved crate");
+        let measured = node
+            .duration_ms
+            .with_context(|| format!("{} was not timed", id.as_str()))?;
+        let factor = duration.factor(measured.into()).with_context(|| {
+            format!(
+                "can't set the compile time of {} to {duration}: it was measured at 0ms",
+                id.as_str()
+            )
+        })?;
+        let scale = |ms: Milliseconds| Milliseconds::from(f64::from(ms) * factor);
+        node.duration_ms = Some(scale(measured));
+        node.rmeta_ms = node.rmeta_ms.map(scale);
+        // The measured time spans the crate's own targets, so only those are
+        // scaled; build scripts keep their time, as in the report.
+        let has_targets = node.units.iter().any(|u| u.kind.stage() >= 2);
+        for unit in &mut node.units {
+            if !has_targets || unit.kind.stage() >= 2 {
+                unit.duration_ms = scale(unit.duration_ms);
+                unit.rmeta_ms = unit.rmeta_ms.map(scale);
+            }
+        }
+    }
+    build::compute_critical_path(&mut graph);
+    Ok(graph)
+}
+
+/// A workspace crate's predicted effect on the build if its compile time
+/// changed.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub id: CrateId,
+    pub duration_ms: Milliseconds,
+    /// Predicted wall time saved; negative if the build would get slower.
+    pub gain_ms: f64,
+    /// Critical path length saved.
+    pub critical_path_gain_ms: f64,
+}
+
+/// Predict, for each timed workspace crate on its own, how much applying
+/// `duration` to it would save, largest gain first.
+pub fn rank(graph: &BuildGraph, duration: DurationOverride, jobs: usize) -> Vec<Candidate> {
+    let base = f64::from(simulate::simulate(graph, jobs).total_ms);
+    let mut candidates: Vec<Candidate> = graph
+        .nodes
+        .values()
+        .filter(|n| n.is_workspace_member)
+        .filter_map(|node| {
+            let duration_ms = node.duration_ms.filter(|d| f64::from(*d) > 0.0)?;
+            let scenario = Scenario {
+                durations: vec![DurationSpec {
+                    krate: node.id.as_str().to_string(),
+                    duration,
+                }],
+                ..Default::default()
+            };
+            let changed = apply(graph, &scenario).ok()?;
+            Some(Candidate {
+                id: node.id.clone(),
+                duration_ms,
+                gain_ms: base - f64::from(simulate::simulate(&changed, jobs).total_ms),
+                critical_path_gain_ms: f64::from(graph.critical_path_ms)
+                    - f64::from(changed.critical_path_ms),
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.gain_ms
+            .total_cmp(&a.gain_ms)
+            .then(b.critical_path_gain_ms.total_cmp(&a.critical_path_gain_ms))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    candidates
+}
+
+/// Move every crate to where a build with `jobs` slots would finish it, so
+/// that `total_ms` is the predicted wall time.
+pub fn predict(graph: &BuildGraph, jobs: usize) -> BuildGraph {
+    let simulation = simulate::simulate(graph, jobs);
+    let mut graph = graph.clone();
+    for node in graph.nodes.values_mut() {
+        if let (Some(predicted), Some(duration)) =
+            (simulation.crates.get(&node.id), node.duration_ms)
+        {
+            node.start_ms = Some(Milliseconds::from(
+                f64::from(predicted.finish_ms) - f64::from(duration),
+            ));
+        }
+    }
+    graph
+}
+
+fn resolve_edge(graph: &BuildGraph, spec: &EdgeSpec) -> anyhow::Result<(CrateId, CrateId)> {
+    Ok((
+        resolve_crate(graph, &spec.from)?,
+        resolve_crate(graph, &spec.to)?,
+    ))
+}
+
+/// Find a crate by ID, or else by name if only one crate has it.
+fn resolve_crate(graph: &BuildGraph, spec: &str) -> anyhow::Result<CrateId> {
+    if let Some(node) = graph.nodes.values().find(|n| n.id.as_str() == spec) {
+        return Ok(node.id.clone());
+    }
+    let mut matches = graph.nodes.values().filter(|n| n.name == spec);
+    let node = matches
+        .next()
+        .with_context(|| format!("no crate `{spec}` in the report"))?;
+    anyhow::ensure!(
+        matches.next().is_none(),
+        "`{spec}` is ambiguous, use `name@version`"
+    );
+    Ok(node.id.clone())
+}
+
+/// Whether `from` depends on `to`, directly or transitively.
+fn depends_on(graph: &BuildGraph, from: &CrateId, to: &CrateId) -> bool {
+    let mut seen: HashSet<&CrateId> = HashSet::new();
+    let mut stack = vec![from];
+    while let Some(id) = stack.pop() {
+        if id == to {
+            return true;
+        }
+        if seen.insert(id) {
+            // Dev-dependencies only gate tests, so they can't close a cycle.
+            stack.extend(
+                graph
+                    .edges
+                    .iter()
+                    .filter(|e| &e.from == id && !e.is_dev_only())
+                    .map(|e| &e.to),
+            );
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::UnitKind;
+    use crate::model::fixtures::{graph, id, timed_graph, unit};
+
+    fn edge(from: &str, to: &str) -> EdgeSpec {
+        EdgeSpec {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn scenarios_parse_from_report_urls() {
+        let scenario = Scenario::from_hash(
+            "file:///t/index.html#sel=a@0.1.0&rm=b@0.1.0~a@0.1.0,c~a&add=c~b&dur=a@0.1.0:0.5x,b:2s",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![edge("b@0.1.0", "a@0.1.0"), edge("c", "a")],
+            scenario.remove
+        );
+        assert_eq!(vec![edge("c", "b")], scenario.add);
+        assert_eq!(
+            vec![
+                DurationSpec {
+                    krate: "a@0.1.0".to_string(),
+                    duration: DurationOverride::Scale(0.5),
+                },
+                DurationSpec {
+                    krate: "b".to_string(),
+                    duration: DurationOverride::Millis(2000.0),
+                },
+            ],
+            scenario.durations
+        );
+        assert!(Scenario::from_hash("dur=a:fast").is_err());
+        assert_eq!(
+            Scenario::default(),
+            Scenario::from_hash("sel=a@0.1.0").unwrap()
+        );
+        assert!(Scenario::from_hash("rm=a").is_err());
+    }
+
+    #[test]
+    fn removing_an_edge_shortens_the_critical_path() {
+        let g = timed_graph(
+            &[("a", 0.0, 2.5), ("b", 0.0, 1.0), ("c", 2.5, 1.0)],
+            &[("c", "a"), ("c", "b")],
+        );
+        let scenario = Scenario {
+            remove: vec![edge("c", "a@0.1.0")],
+            ..Default::default()
+        };
+        let changed = apply(&g, &scenario).unwrap();
+        assert_eq!(Milliseconds::from(2500.0), changed.critical_path_ms);
+        assert_eq!(vec![id("a")], changed.critical_path);
+        assert_eq!(Milliseconds::from(2500.0), predict(&changed, 2).total_ms());
+    }
+
+    #[test]
+    fn edges_that_would_create_cycles_are_rejected() {
+        let g = timed_graph(
+            &[("a", 0.0, 1.0), ("b", 1.0, 1.0), ("c", 2.0, 1.0)],
+            &[("b", "a"), ("c", "b")],
+        );
+        let add = |from: &str, to: &str| Scenario {
+            add: vec![edge(from, to)],
+            ..Default::default()
+        };
+        let err = apply(&g, &add("a", "c")).unwrap_err();
+        assert!(err.to_string().contains("would create a cycle"));
+        assert!(apply(&g, &add("a", "a")).is_err());
+        assert!(apply(&g, &add("c", "a")).is_ok());
+        // Once c no longer depends on b, a may depend on c.
+        let scenario = Scenario {
+            remove: vec![edge("c", "b")],
+            add: vec![edge("a", "c")],
+            ..Default::default()
+        };
+        let changed = apply(&g, &scenario).unwrap();
+        assert_eq!(vec![id("c"), id("a"), id("b")], changed.critical_path);
+        assert!(apply(&g, &add("x", "a")).is_err());
+    }
+
+    #[test]
+    fn changed_compile_times_are_ranked_by_gain() {
+        let g = timed_graph(
+            &[("a", 0.0, 2.0), ("b", 0.0, 1.0), ("c", 2.0, 1.0)],
+            &[("c", "a"), ("c", "b")],
+        );
+        let scenario = Scenario {
+            durations: vec!["a:0.25x".parse().unwrap(), "c:3s".parse().unwrap()],
+            ..Default::default()
+        };
+        let changed = apply(&g, &scenario).unwrap();
+        assert_eq!(
+            Some(Milliseconds::from(500.0)),
+            changed.nodes[&id("a")].duration_ms
+        );
+        // b (1s) now gates c (3s).
+        assert_eq!(Milliseconds::from(4000.0), changed.critical_path_ms);
+        assert_eq!(vec![id("b"), id("c")], changed.critical_path);
+
+        // The last compile time given for a crate wins.
+        let scenario = Scenario {
+            durations: vec!["a:0.5x".parse().unwrap(), "a:0.5x".parse().unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(
+            Some(Milliseconds::from(1000.0)),
+            apply(&g, &scenario).unwrap().nodes[&id("a")].duration_ms
+        );
+
+        let ranking = rank(&g, DurationOverride::Scale(0.5), 2);
+        let gains: Vec<(CrateId, f64)> =
+            ranking.iter().map(|c| (c.id.clone(), c.gain_ms)).collect();
+        // Halving a saves a whole second, as b then gates c as well; halving
+        // b saves nothing.
+        assert_eq!(
+            vec![(id("a"), 1000.0), (id("c"), 500.0), (id("b"), 0.0)],
+            gains
+        );
+    }
+
+    #[test]
+    fn set_compile_times_leave_build_scripts_alone() {
+        let mut g = graph(&["a", "b"], &[]);
+        build::apply_timings(
+            &mut g,
+            &[
+                unit("a", " build-script", 0.0, 0.1),
+                unit("a", " build-script (run)", 0.1, 0.1),
+                unit("a", "", 0.2, 1.0),
+                unit("b", "", 0.0, 0.0),
+            ],
+        );
+        let scenario = Scenario::from_hash("dur=a:500ms").unwrap();
+        let changed = apply(&g, &scenario).unwrap();
+        let a = &changed.nodes[&id("a")];
+        assert_eq!(Some(Milliseconds::from(500.0)), a.duration_ms);
+        let durations: Vec<(UnitKind, f64)> = a
+            .units
+            .iter()
+            .map(|u| (u.kind, f64::from(u.duration_ms)))
+            .collect();
+        assert_eq!(
+            vec![
+                (UnitKind::BuildScript, 100.0),
+                (UnitKind::BuildScriptRun, 100.0),
+                (UnitKind::Lib, 500.0),
+            ],
+            durations
+        );
+        assert_eq!(Milliseconds::from(700.0), changed.critical_path_ms);
+
+        // b took no time, so there is nothing to stretch to 800ms.
+        let err = apply(&g, &Scenario::from_hash("dur=b:800ms").unwrap()).unwrap_err();
+        assert!(err.to_string().contains("b@0.1.0"), "{err}");
+        assert!(apply(&g, &Scenario::from_hash("dur=b:2x").unwrap()).is_ok());
+    }
+
+    /// A scenario and its outcome, shared with the report's what-if view
+    /// (`whatif.test.ts`) so that both predict the same build.
+    #[derive(serde::Deserialize)]
+    struct SharedScenario {
+        crates: Vec<(String, f64, f64)>,
+        edges: Vec<(String, String, String)>,
+        hash: String,
+        critical_path: Vec<String>,
+        start_ms: std::collections::BTreeMap<String, f64>,
+    }
+
+    #[test]
+    fn report_hashes_replay_like_the_report() {
+        let shared: SharedScenario =
+            serde_json::from_str(include_str!("../frontend/src/lib/whatif.fixture.json")).unwrap();
+        let names: Vec<&str> = shared.crates.iter().map(|c| c.0.as_str()).collect();
+        let edges: Vec<(&str, &str, &str)> = shared
+            .edges
+            .iter()
+            .map(|(from, to, kind)| (from.as_str(), to.as_str(), kind.as_str()))
+            .collect();
+        let mut g = graph(&names, &edges);
+        let units: Vec<_> = shared
+            .crates
+            .iter()
+            .map(|(name, start, duration)| unit(name, "", start / 1000.0, duration / 1000.0))
+            .collect();
+        build::apply_timings(&mut g, &units);
+
+        let changed = apply(&g, &Scenario::from_hash(&shared.hash).unwrap()).unwrap();
+        let expected: Vec<CrateId> = shared.critical_path.iter().map(|n| id(n)).collect();
+        assert_eq!(expected, changed.critical_path);
+        let predicted = predict(&changed, names.len());
+        for (name, start) in &shared.start_ms {
+            assert_eq!(
+                Some(Milliseconds::from(*start)),
+                predicted.nodes[&id(name)].start_ms,
+                "{name}"
+            );
+        }
+    }
+}